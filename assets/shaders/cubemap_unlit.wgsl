@@ -0,0 +1,15 @@
+#import bevy_pbr::mesh_view_bindings
+
+@group(1) @binding(0)
+var base_color_texture: texture_cube<f32>;
+@group(1) @binding(1)
+var base_color_sampler: sampler;
+
+// the sky cube is centred on the board, so the world position is the view direction
+@fragment
+fn fragment(
+    #import bevy_pbr::mesh_vertex_output
+) -> @location(0) vec4<f32> {
+    let direction_lh = world_position.xyz * vec3<f32>(1.0, 1.0, -1.0);
+    return textureSample(base_color_texture, base_color_sampler, direction_lh);
+}
@@ -0,0 +1,115 @@
+#import bevy_pbr::mesh_view_bindings
+#import bevy_pbr::pbr_types
+#import bevy_pbr::mesh_bindings
+
+#import bevy_pbr::utils
+#import bevy_pbr::clustered_forward
+#import bevy_pbr::lighting
+#import bevy_pbr::shadows
+#import bevy_pbr::pbr_functions
+
+struct StoneMaterial {
+    base_color: vec4<f32>,
+    perceptual_roughness: f32,
+    reflectance: f32,
+    flags: u32,
+    alpha_cutoff: f32,
+    environment_intensity: f32,
+};
+
+@group(1) @binding(0)
+var<uniform> material: StoneMaterial;
+@group(1) @binding(1)
+var base_color_texture: texture_2d<f32>;
+@group(1) @binding(2)
+var base_color_sampler: sampler;
+@group(1) @binding(3)
+var environment_texture: texture_cube<f32>;
+@group(1) @binding(4)
+var environment_sampler: sampler;
+
+struct FragmentInput {
+    @builtin(front_facing) is_front: bool,
+    @builtin(position) frag_coord: vec4<f32>,
+    #import bevy_pbr::mesh_vertex_output
+};
+
+// the sky seen in a world direction, flipped the same way as the skybox
+fn sky(direction: vec3<f32>) -> vec3<f32> {
+    let direction_lh = direction * vec3<f32>(1.0, 1.0, -1.0);
+    return textureSampleLevel(environment_texture, environment_sampler, direction_lh, 0.0).rgb;
+}
+
+// the sky averaged over a cone of half angle `spread` around `axis`, the cubemaps have no mips to blur with
+fn sky_cone(axis: vec3<f32>, spread: f32) -> vec3<f32> {
+    let up = select(vec3<f32>(0.0, 1.0, 0.0), vec3<f32>(1.0, 0.0, 0.0), abs(axis.y) > 0.99);
+    let tangent = normalize(cross(up, axis));
+    let bitangent = cross(axis, tangent);
+    var sum = sky(axis);
+    // two rings of eight, the inner one turned half a step
+    for (var i = 0; i < 8; i = i + 1) {
+        let angle = f32(i) * 0.7853982;
+        let inner = tangent * cos(angle + 0.3926991) + bitangent * sin(angle + 0.3926991);
+        let outer = tangent * cos(angle) + bitangent * sin(angle);
+        sum = sum + sky(normalize(axis + inner * tan(spread * 0.5)));
+        sum = sum + sky(normalize(axis + outer * tan(spread)));
+    }
+    return sum / 17.0;
+}
+
+@fragment
+fn fragment(in: FragmentInput) -> @location(0) vec4<f32> {
+    var output_color: vec4<f32> = material.base_color;
+#ifdef VERTEX_UVS
+    if ((material.flags & STANDARD_MATERIAL_FLAGS_BASE_COLOR_TEXTURE_BIT) != 0u) {
+        output_color = output_color * textureSample(base_color_texture, base_color_sampler, in.uv);
+    }
+#endif
+
+    var pbr_input: PbrInput = pbr_input_new();
+    pbr_input.material.base_color = output_color;
+    pbr_input.material.perceptual_roughness = material.perceptual_roughness;
+    pbr_input.material.reflectance = material.reflectance;
+    pbr_input.material.flags = material.flags;
+    pbr_input.material.alpha_cutoff = material.alpha_cutoff;
+
+    if ((material.flags & STANDARD_MATERIAL_FLAGS_UNLIT_BIT) == 0u) {
+        pbr_input.frag_coord = in.frag_coord;
+        pbr_input.world_position = in.world_position;
+        pbr_input.world_normal = prepare_world_normal(in.world_normal, false, in.is_front);
+        pbr_input.is_orthographic = view.projection[3].w == 1.0;
+        pbr_input.N = normalize(pbr_input.world_normal);
+        pbr_input.V = calculate_view(in.world_position, pbr_input.is_orthographic);
+        output_color = pbr(pbr_input);
+
+        // light from the sky, diffuse from around the normal and specular from around the reflection,
+        // rougher stones see a wider patch of it
+        let N = pbr_input.N;
+        let V = pbr_input.V;
+        let NdotV = max(dot(N, V), 0.0001);
+        let metallic = pbr_input.material.metallic;
+        let perceptual_roughness = clamp(material.perceptual_roughness, 0.089, 1.0);
+        let base_color = pbr_input.material.base_color.rgb;
+        let F0 = 0.16 * material.reflectance * material.reflectance * (1.0 - metallic) + base_color * metallic;
+        let diffuse_color = base_color * (1.0 - metallic);
+        let irradiance = sky_cone(N, 1.2);
+        let radiance = sky_cone(reflect(-V, N), perceptual_roughness * 1.2);
+        let environment_light = EnvBRDFApprox(diffuse_color, 1.0, NdotV) * irradiance
+            + EnvBRDFApprox(F0, perceptual_roughness, NdotV) * radiance;
+        output_color = vec4<f32>(output_color.rgb + environment_light * material.environment_intensity, output_color.a);
+    } else {
+        output_color = alpha_discard(pbr_input.material, output_color);
+    }
+
+#ifdef TONEMAP_IN_SHADER
+    output_color = tone_mapping(output_color);
+#endif
+#ifdef DEBAND_DITHER
+    var output_rgb = output_color.rgb;
+    output_rgb = pow(output_rgb, vec3<f32>(1.0 / 2.2));
+    output_rgb = output_rgb + screen_space_dither(in.frag_coord.xy);
+    output_rgb = pow(output_rgb, vec3<f32>(2.2));
+    output_color = vec4(output_rgb, output_color.a);
+#endif
+    return output_color;
+}
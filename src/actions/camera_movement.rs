@@ -1,12 +1,137 @@
-use crate::game_state::GameState;
-use crate::actions::{key_input::KeyInputState,mouse_input::MouseCamera,mouse_input::MouseLookState};
-use bevy::prelude::*;
+use crate::game_state::{GameMode, GameState};
+use crate::actions::{gamepad_input::gamepad_to_look,touch_input::touch_to_actions,key_input::KeyInputState,mouse_input::{input_to_look,MouseCamera,MouseLookState,ORBIT_PITCH_BOUNDS}};
+use crate::board::{BoardState, GameBoard, SPACING, space_to_world};
+use bevy::{prelude::*, render::camera::ScalingMode};
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
 
-const CAMERA_FLY_MOVE_SPEED: f32 = 1.0;
+// camera distance per unit of framed extent, matches the default view of the full board
+const CAMERA_FRAME_DISTANCE: f32 = 1.2;
+// orbit distance at zoom 0, the whole 19x19 board in view
+const ORBIT_DISTANCE: f32 = 0.36;
+const ORBIT_MIN_DISTANCE: f32 = 0.06;
+const ORBIT_MAX_DISTANCE: f32 = 1.2;
+// seconds to glide from one preset view to the next
+const PRESET_TRANSITION_SECONDS: f32 = 0.8;
+// height of the diagram view per unit of orbit distance, the board appears as large as in perspective
+const DIAGRAM_HEIGHT: f32 = 0.83;
+// the diagram only needs the board in depth, the sky cube is left beyond the far plane
+// and the depth buffer keeps its precision for the thin stones
+const DIAGRAM_FAR: f32 = 10.;
+
+// named views of the board, the seats are black's on +z and white's on -z, each with their bowl to the right
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CameraPreset {
+    TopDown,
+    Seat(usize),
+    Side,
+    Corner,
+}
+
+impl CameraPreset {
+    // where the preset puts the orbit camera over a board of `size`
+    pub fn view(self, size: usize) -> OrbitView {
+        let extent = SPACING * (size - 1) as f32;
+        match self {
+            CameraPreset::TopDown => OrbitView {
+                center: Vec3::ZERO,
+                yaw: 0.,
+                pitch: ORBIT_PITCH_BOUNDS.0,
+                zoom: orbit_zoom(extent * 1.55),
+            },
+            CameraPreset::Seat(player) => OrbitView {
+                center: Vec3::ZERO,
+                yaw: if player == 0 { 0. } else { PI },
+                pitch: -1.05,
+                zoom: orbit_zoom(extent * 1.35),
+            },
+            // low along the edge between the bowls
+            CameraPreset::Side => OrbitView {
+                center: Vec3::ZERO,
+                yaw: FRAC_PI_2,
+                pitch: -0.3,
+                zoom: orbit_zoom(extent * 1.6),
+            },
+            // close over the star point of black's right corner, looking in from outside the board
+            CameraPreset::Corner => {
+                let line = if size >= 13 { 3 } else { 2 };
+                let center = space_to_world((size - 1 - line, size - 1 - line), size);
+                OrbitView {
+                    center,
+                    yaw: center.x.atan2(center.z),
+                    pitch: -0.75,
+                    zoom: orbit_zoom(0.12),
+                }
+            },
+        }
+    }
+}
+
+// a placement of the orbit camera, in the angles of `MouseLookState`
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OrbitView {
+    pub center: Vec3,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub zoom: f32,
+}
+
+#[derive(Default)]
+pub struct CameraTransition {
+    pub from: OrbitView,
+    pub to: OrbitView,
+    pub timer: Timer,
+}
 
 // system state
 #[derive(Default, Resource)]
 pub struct CamerasState {
+    // the point the orbit camera turns around, the board centre unless something was framed
+    pub orbit_center: Vec3,
+    // the preset last picked, until the camera is dragged or framed elsewhere
+    pub preset: Option<CameraPreset>,
+    pub transition: Option<CameraTransition>,
+    // while the orthographic diagram is shown, the view to go back to
+    pub diagram: Option<OrbitView>,
+}
+
+impl CamerasState {
+    pub fn is_diagram(&self) -> bool {
+        self.diagram.is_some()
+    }
+
+    fn orbit_view(&self, mouse_look: &MouseLookState) -> OrbitView {
+        OrbitView {
+            center: self.orbit_center,
+            yaw: mouse_look.yaw_pitch_roll.x,
+            pitch: mouse_look.yaw_pitch_roll.y,
+            zoom: mouse_look.zoom,
+        }
+    }
+
+    fn start_transition(&mut self, from: OrbitView, mut to: OrbitView) {
+        // turns the short way round
+        to.yaw = from.yaw + (to.yaw - from.yaw + PI).rem_euclid(TAU) - PI;
+        self.transition = Some(CameraTransition {
+            from,
+            to,
+            timer: Timer::from_seconds(PRESET_TRANSITION_SECONDS, TimerMode::Once),
+        });
+    }
+}
+
+// distance of the orbit camera from its centre for a zoom level, zoom is logarithmic so each wheel step scales alike
+pub fn orbit_distance(zoom: f32) -> f32 {
+    ORBIT_DISTANCE * zoom.exp()
+}
+
+fn orbit_zoom(distance: f32) -> f32 {
+    (distance.clamp(ORBIT_MIN_DISTANCE, ORBIT_MAX_DISTANCE) / ORBIT_DISTANCE).ln()
+}
+
+// moves the camera back along its view direction until the area around `center` fills the view
+pub struct FrameCameraEvent {
+    pub center: Vec3,
+    pub extent: f32,
 }
 
 pub struct CamerasStatePlugin;
@@ -14,33 +139,147 @@ pub struct CamerasStatePlugin;
 impl Plugin for CamerasStatePlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<CamerasState>();
+        app.add_event::<FrameCameraEvent>();
+        app.add_system_set(
+            SystemSet::on_enter(GameState::Running)
+            .with_system(reset_camera_orbit)
+        );
         app.add_system_set(
             SystemSet::on_update(GameState::Running)
-            .with_system(update_camera_movement)
+            .with_system(update_camera_framing)
+            .with_system(update_diagram_view.after(update_camera_framing))
+            .with_system(update_camera_presets.after(update_diagram_view).after(input_to_look).after(gamepad_to_look).after(touch_to_actions))
+            .with_system(update_camera_movement.after(update_camera_presets))
         );
     }
 }
 
-// update camera position from movement
-fn update_camera_movement(
+// each game starts orbiting the board centre at the default distance
+fn reset_camera_orbit(
+    mut cameras_state: ResMut<CamerasState>,
+    mut mouse_look: ResMut<MouseLookState>,
+) {
+    cameras_state.orbit_center = Vec3::ZERO;
+    cameras_state.preset = None;
+    cameras_state.transition = None;
+    mouse_look.zoom = 0.;
+}
+
+// starts transitions to picked presets and eases the orbit camera along them
+#[allow(clippy::too_many_arguments)]
+fn update_camera_presets(
     time: Res<Time>,
     key_state: Res<KeyInputState>,
+    game_mode: Res<GameMode>,
+    board_state: Res<BoardState>,
+    game_board: Res<GameBoard>,
+    mouse_btn_input: Res<Input<MouseButton>>,
+    mut cameras_state: ResMut<CamerasState>,
+    mut mouse_look: ResMut<MouseLookState>,
+) {
+    // dragging takes the camera back from the preset
+    if mouse_btn_input.just_pressed(MouseButton::Right) {
+        cameras_state.preset = None;
+        cameras_state.transition = None;
+    }
+
+    let mut next_preset = key_state.camera_preset;
+    // in a local game the seat view turns to whoever is to move
+    if let (None, GameMode::Free, Some(CameraPreset::Seat(player))) = (next_preset, *game_mode, cameras_state.preset) {
+        if player != board_state.player_turn {
+            next_preset = Some(CameraPreset::Seat(board_state.player_turn));
+        }
+    }
+    if let Some(preset) = next_preset {
+        let from = cameras_state.orbit_view(&mouse_look);
+        cameras_state.preset = Some(preset);
+        cameras_state.start_transition(from, preset.view(game_board.size));
+    }
+
+    let Some(transition) = cameras_state.transition.as_mut() else {
+        return;
+    };
+    let t = transition.timer.tick(time.delta()).percent();
+    let eased = t * t * (3. - 2. * t);
+    let (from, to) = (transition.from, transition.to);
+    let finished = transition.timer.finished();
+    cameras_state.orbit_center = from.center.lerp(to.center, eased);
+    mouse_look.yaw_pitch_roll.x = from.yaw + (to.yaw - from.yaw) * eased;
+    mouse_look.yaw_pitch_roll.y = from.pitch + (to.pitch - from.pitch) * eased;
+    mouse_look.zoom = from.zoom + (to.zoom - from.zoom) * eased;
+    mouse_look.update_vectors();
+    if finished {
+        cameras_state.transition = None;
+    }
+}
+
+// switches between the perspective camera and the flat top-down diagram
+fn update_diagram_view(
+    key_state: Res<KeyInputState>,
+    mut cameras_state: ResMut<CamerasState>,
     mouse_look: Res<MouseLookState>,
+    mut query: Query<&mut Projection, With<MouseCamera>>,
+) {
+    if !key_state.toggle_diagram {
+        return;
+    }
+    let mut projection = query.single_mut();
+    let current = cameras_state.orbit_view(&mouse_look);
+    if let Some(view) = cameras_state.diagram.take() {
+        *projection = Projection::Perspective(default());
+        cameras_state.start_transition(current, view);
+    } else {
+        *projection = Projection::Orthographic(OrthographicProjection {
+            far: DIAGRAM_FAR,
+            scaling_mode: ScalingMode::FixedVertical(1.),
+            scale: orbit_distance(current.zoom) * DIAGRAM_HEIGHT,
+            ..default()
+        });
+        cameras_state.diagram = Some(current);
+        cameras_state.transition = None;
+    }
+}
+
+// orbits the board centre at the zoomed distance
+fn update_camera_movement(
+    cameras_state: Res<CamerasState>,
+    mut mouse_look: ResMut<MouseLookState>,
+    mut query: Query<(&mut Transform, &mut Projection), With<MouseCamera>>,
+) {
+    let (mut camera, mut projection) = query.single_mut();
+    let zoom = mouse_look.zoom.clamp(orbit_zoom(ORBIT_MIN_DISTANCE), orbit_zoom(ORBIT_MAX_DISTANCE));
+    if zoom != mouse_look.zoom {
+        mouse_look.zoom = zoom;
+    }
+    // the diagram can be turned and zoomed but always looks straight down
+    if cameras_state.is_diagram() && mouse_look.yaw_pitch_roll.y != ORBIT_PITCH_BOUNDS.0 {
+        mouse_look.yaw_pitch_roll.y = ORBIT_PITCH_BOUNDS.0;
+        mouse_look.update_vectors();
+    }
+    if let Projection::Orthographic(orthographic) = projection.as_mut() {
+        let scale = orbit_distance(zoom) * DIAGRAM_HEIGHT;
+        if orthographic.scale != scale {
+            orthographic.scale = scale;
+        }
+    }
+    camera.translation = cameras_state.orbit_center - mouse_look.forward * orbit_distance(zoom);
+    camera.look_at(cameras_state.orbit_center, Vec3::Y);
+}
+
+// jump the camera to frame a requested area, the orbit camera then turns around it
+fn update_camera_framing(
+    mut frame_events: EventReader<FrameCameraEvent>,
+    mut cameras_state: ResMut<CamerasState>,
+    mut mouse_look: ResMut<MouseLookState>,
     mut query: Query<&mut Transform, With<MouseCamera>>,
 ) {
-    let mut camera = query.single_mut();
-    let run_mult = if key_state.run { 5.0 } else { 1.0 };
-
-    let camera_move = CAMERA_FLY_MOVE_SPEED * run_mult * time.delta_seconds() * (
-        if key_state.forward { mouse_look.forward.clone() } else { Vec3::ZERO } +
-        if key_state.backward { -mouse_look.forward.clone() } else { Vec3::ZERO } +
-        if key_state.right { mouse_look.right.clone() } else { Vec3::ZERO } +
-        if key_state.left { -mouse_look.right.clone() } else { Vec3::ZERO } +
-        if key_state.up { mouse_look.up.clone() } else { Vec3::ZERO } +
-        if key_state.down { -mouse_look.up.clone() } else { Vec3::ZERO }
-    );
-
-    let next_position = camera.translation + camera_move;
-    camera.translation = next_position.clone();
-    camera.look_at(next_position + mouse_look.forward, Vec3::Y);
+    if let Some(frame) = frame_events.iter().last() {
+        let mut camera = query.single_mut();
+        let distance = frame.extent * CAMERA_FRAME_DISTANCE;
+        camera.translation = frame.center - mouse_look.forward * distance;
+        cameras_state.orbit_center = frame.center;
+        cameras_state.preset = None;
+        cameras_state.transition = None;
+        mouse_look.zoom = orbit_zoom(distance);
+    }
 }
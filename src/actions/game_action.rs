@@ -0,0 +1,43 @@
+use crate::actions::{gamepad_input::gamepad_to_actions, key_input::input_to_move, mouse_input::update_cursor_lock_wasm_running};
+use crate::game_state::GameState;
+use bevy::{prelude::*, ecs::schedule::StateError};
+
+// what a player does in the game, sent by the mouse, keys, gamepads and touch alike, and by anything
+// else playing for the player to move such as a bot or a network peer, the rules only act on these,
+// stones and passes the board rules turn down come back as a `GameActionRejected`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameAction {
+    // a stone for the player to move, on a point given in lines
+    PlaceStone((usize,usize)),
+    Pass,
+    // takes back the move shown, handled by the game record so only in free play
+    Undo,
+    Pause,
+}
+
+pub struct GameActionPlugin;
+
+impl Plugin for GameActionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<GameAction>();
+        app.add_system_set(
+            SystemSet::on_update(GameState::Running)
+            .with_system(pause_game.after(input_to_move).after(gamepad_to_actions).after(update_cursor_lock_wasm_running))
+        );
+    }
+}
+
+// pauses once, even when several inputs asked for it in the same frame
+fn pause_game(
+    mut actions: EventReader<GameAction>,
+    mut game_state: ResMut<State<GameState>>,
+) {
+    if actions.iter().filter(|action| **action == GameAction::Pause).count() == 0 {
+        return;
+    }
+    match game_state.push(GameState::Paused) {
+        // already paused, or another state change was asked for first
+        Ok(()) | Err(StateError::AlreadyInState | StateError::StateAlreadyQueued) => {}
+        Err(err) => warn!("Could not pause: {:?}", err),
+    }
+}
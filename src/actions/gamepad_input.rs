@@ -0,0 +1,134 @@
+use crate::actions::camera_movement::CamerasState;
+use crate::actions::game_action::GameAction;
+use crate::actions::MouseLookState;
+use crate::actions::mouse_input::ORBIT_PITCH_BOUNDS;
+use crate::game_state::GameState;
+use bevy::prelude::*;
+
+// how far a stick has to be pushed to step the cursor
+const CURSOR_STICK_THRESHOLD: f32 = 0.5;
+// seconds a stick is held before the cursor keeps stepping, and between those steps
+const CURSOR_REPEAT_DELAY: f32 = 0.35;
+const CURSOR_REPEAT_INTERVAL: f32 = 0.12;
+// radians per second at full tilt of the right stick
+const LOOK_SPEED: Vec2 = Vec2::new(2.2, 1.4);
+const LOOK_DEADZONE: f32 = 0.15;
+// zoom per second with a trigger fully pressed
+const ZOOM_SPEED: f32 = 1.5;
+
+// the board cursor moves read from the gamepads this frame, merged into `KeyInputState` with the keys
+#[derive(Default, Resource)]
+pub struct GamepadInputState {
+    pub cursor_step: IVec2,
+    pub play_move: bool,
+    // the direction the left stick is held in and the seconds until it steps the cursor again
+    held_direction: IVec2,
+    repeat_timer: f32,
+}
+
+pub struct GamepadInputPlugin;
+
+impl Plugin for GamepadInputPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GamepadInputState>();
+        app.add_system_set(
+            SystemSet::on_update(GameState::Running)
+            .with_system(gamepad_to_actions)
+            .with_system(gamepad_to_look)
+        );
+    }
+}
+
+// face buttons play, pass and undo, start pauses, the left stick and the d-pad step the board cursor
+pub fn gamepad_to_actions(
+    time: Res<Time>,
+    gamepads: Res<Gamepads>,
+    button_input: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    mut state: ResMut<GamepadInputState>,
+    mut actions: EventWriter<GameAction>,
+) {
+    let just_pressed = |button_type: GamepadButtonType| {
+        gamepads.iter().any(|gamepad| button_input.just_pressed(GamepadButton::new(gamepad, button_type)))
+    };
+    state.play_move = just_pressed(GamepadButtonType::South);
+    for (button_type, action) in [
+        (GamepadButtonType::West, GameAction::Pass),
+        (GamepadButtonType::East, GameAction::Undo),
+        (GamepadButtonType::Start, GameAction::Pause),
+    ] {
+        if just_pressed(button_type) {
+            actions.send(action);
+        }
+    }
+
+    state.cursor_step = IVec2::ZERO;
+    for (button_type, step) in [
+        (GamepadButtonType::DPadUp, IVec2::Y),
+        (GamepadButtonType::DPadDown, IVec2::NEG_Y),
+        (GamepadButtonType::DPadRight, IVec2::X),
+        (GamepadButtonType::DPadLeft, IVec2::NEG_X),
+    ] {
+        if just_pressed(button_type) {
+            state.cursor_step += step;
+        }
+    }
+
+    // a held stick steps once, then keeps stepping after a moment, along its main axis
+    let stick = gamepads.iter().map(|gamepad| Vec2::new(
+        axes.get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickX)).unwrap_or(0.),
+        axes.get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickY)).unwrap_or(0.),
+    )).fold(Vec2::ZERO, |held, stick| if stick.length() > held.length() { stick } else { held });
+    let direction = if stick.length() < CURSOR_STICK_THRESHOLD {
+        IVec2::ZERO
+    } else if stick.x.abs() > stick.y.abs() {
+        IVec2::new(stick.x.signum() as i32, 0)
+    } else {
+        IVec2::new(0, stick.y.signum() as i32)
+    };
+    if direction != state.held_direction {
+        state.held_direction = direction;
+        state.repeat_timer = CURSOR_REPEAT_DELAY;
+        state.cursor_step += direction;
+    } else if direction != IVec2::ZERO {
+        state.repeat_timer -= time.delta_seconds();
+        if state.repeat_timer <= 0. {
+            state.repeat_timer = CURSOR_REPEAT_INTERVAL;
+            state.cursor_step += direction;
+        }
+    }
+}
+
+// the right stick turns the orbit camera and the triggers zoom it
+pub fn gamepad_to_look(
+    time: Res<Time>,
+    gamepads: Res<Gamepads>,
+    axes: Res<Axis<GamepadAxis>>,
+    button_axes: Res<Axis<GamepadButton>>,
+    mut cameras_state: ResMut<CamerasState>,
+    mut mouse_look: ResMut<MouseLookState>,
+) {
+    let mut look = Vec2::ZERO;
+    let mut zoom = 0.;
+    for gamepad in gamepads.iter() {
+        look += Vec2::new(
+            axes.get(GamepadAxis::new(gamepad, GamepadAxisType::RightStickX)).unwrap_or(0.),
+            axes.get(GamepadAxis::new(gamepad, GamepadAxisType::RightStickY)).unwrap_or(0.),
+        );
+        let trigger = |button_type| button_axes.get(GamepadButton::new(gamepad, button_type)).unwrap_or(0.);
+        zoom += trigger(GamepadButtonType::LeftTrigger2) - trigger(GamepadButtonType::RightTrigger2);
+    }
+
+    if zoom != 0. {
+        mouse_look.zoom += zoom * ZOOM_SPEED * time.delta_seconds();
+    }
+    if look.length() > LOOK_DEADZONE {
+        // turning by hand leaves the camera preset
+        cameras_state.preset = None;
+        cameras_state.transition = None;
+        let turn = -look * LOOK_SPEED * time.delta_seconds();
+        mouse_look.yaw_pitch_roll.x += turn.x;
+        mouse_look.yaw_pitch_roll.y = (mouse_look.yaw_pitch_roll.y - turn.y).clamp(ORBIT_PITCH_BOUNDS.0, ORBIT_PITCH_BOUNDS.1);
+        mouse_look.update_vectors();
+    }
+}
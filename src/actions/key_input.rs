@@ -1,20 +1,51 @@
 use bevy::{prelude::*};
-use crate::actions::mouse_input::CursorLockState;
-use crate::game_state::GameState;
+use crate::actions::{camera_movement::CameraPreset, game_action::GameAction, gamepad_input::{GamepadInputState, gamepad_to_actions}, keymap::load_keymap};
+use crate::board::TreeStep;
+use crate::game_state::{GameMode, GameState};
+
+// the arrow keys also step the board cursor in the modes that have no game record to move through
+const CURSOR_ARROWS: [(KeyCode, IVec2); 4] = [
+    (KeyCode::Up, IVec2::Y),
+    (KeyCode::Down, IVec2::NEG_Y),
+    (KeyCode::Right, IVec2::X),
+    (KeyCode::Left, IVec2::NEG_X),
+];
 
 // maintains mappings from input actions to game-logic actions
-#[derive(Debug, Resource)]
+#[derive(Clone, Debug, Resource)]
 pub struct KeyInputMap {
     pub key_forward: KeyCode,
     pub key_backward: KeyCode,
     pub key_left: KeyCode,
     pub key_right: KeyCode,
-    pub key_jump: KeyCode,
-    pub key_run: KeyCode,
-    pub key_crouch: KeyCode,
-    pub key_fly: KeyCode,
-    pub key_toggleview: KeyCode,
     pub key_escape: KeyCode,
+    pub key_assist: KeyCode,
+    pub key_liberties: KeyCode,
+    pub key_ladder_path: KeyCode,
+    pub key_move_numbers: KeyCode,
+    pub key_move_number_range: KeyCode,
+    pub key_coordinates: KeyCode,
+    pub key_stone_shape: KeyCode,
+    pub key_theme: KeyCode,
+    pub key_environment: KeyCode,
+    pub key_problem_next: KeyCode,
+    pub key_problem_previous: KeyCode,
+    pub key_problem_retry: KeyCode,
+    pub key_review_back: KeyCode,
+    pub key_review_forward: KeyCode,
+    pub key_review_start: KeyCode,
+    pub key_review_end: KeyCode,
+    pub key_branch_next: KeyCode,
+    pub key_branch_previous: KeyCode,
+    pub key_view_top_down: KeyCode,
+    pub key_view_black_seat: KeyCode,
+    pub key_view_white_seat: KeyCode,
+    pub key_view_side: KeyCode,
+    pub key_view_corner: KeyCode,
+    pub key_diagram: KeyCode,
+    pub key_play: KeyCode,
+    pub key_pass: KeyCode,
+    pub key_undo: KeyCode,
 }
 
 impl Default for KeyInputMap {
@@ -24,29 +55,112 @@ impl Default for KeyInputMap {
             key_backward: KeyCode::S,
             key_left: KeyCode::A,
             key_right: KeyCode::D,
-            key_jump: KeyCode::Space,
-            key_run: KeyCode::LShift,
-            key_crouch: KeyCode::LControl,
-            key_fly: KeyCode::F,
-            key_toggleview: KeyCode::T,
             key_escape: KeyCode::Escape,
+            key_assist: KeyCode::F1,
+            key_liberties: KeyCode::F2,
+            key_ladder_path: KeyCode::F3,
+            key_move_numbers: KeyCode::F4,
+            key_move_number_range: KeyCode::F5,
+            key_coordinates: KeyCode::F6,
+            key_stone_shape: KeyCode::F7,
+            key_theme: KeyCode::F8,
+            key_environment: KeyCode::F9,
+            key_problem_next: KeyCode::N,
+            key_problem_previous: KeyCode::B,
+            key_problem_retry: KeyCode::R,
+            key_review_back: KeyCode::Left,
+            key_review_forward: KeyCode::Right,
+            key_review_start: KeyCode::Home,
+            key_review_end: KeyCode::End,
+            key_branch_next: KeyCode::Down,
+            key_branch_previous: KeyCode::Up,
+            key_view_top_down: KeyCode::Key1,
+            key_view_black_seat: KeyCode::Key2,
+            key_view_white_seat: KeyCode::Key3,
+            key_view_side: KeyCode::Key4,
+            key_view_corner: KeyCode::Key5,
+            key_diagram: KeyCode::Tab,
+            key_play: KeyCode::Return,
+            key_pass: KeyCode::P,
+            key_undo: KeyCode::U,
         }
     }
 }
 
+impl KeyInputMap {
+    // every binding with its name in the keymap file and its label on the controls screen
+    pub fn bindings_mut(&mut self) -> [(&'static str, &'static str, &mut KeyCode); 32] {
+        [
+            ("forward", "Cursor up", &mut self.key_forward),
+            ("backward", "Cursor down", &mut self.key_backward),
+            ("left", "Cursor left", &mut self.key_left),
+            ("right", "Cursor right", &mut self.key_right),
+            ("pause", "Pause", &mut self.key_escape),
+            ("play", "Play at cursor", &mut self.key_play),
+            ("pass", "Pass", &mut self.key_pass),
+            ("undo", "Undo", &mut self.key_undo),
+            ("review_back", "Previous move", &mut self.key_review_back),
+            ("review_forward", "Next move", &mut self.key_review_forward),
+            ("review_start", "First move", &mut self.key_review_start),
+            ("review_end", "Last move", &mut self.key_review_end),
+            ("branch_next", "Next variation", &mut self.key_branch_next),
+            ("branch_previous", "Previous variation", &mut self.key_branch_previous),
+            ("problem_next", "Next problem", &mut self.key_problem_next),
+            ("problem_previous", "Previous problem", &mut self.key_problem_previous),
+            ("problem_retry", "Retry problem", &mut self.key_problem_retry),
+            ("assist", "Assist", &mut self.key_assist),
+            ("liberties", "Liberties", &mut self.key_liberties),
+            ("ladder_path", "Ladder path", &mut self.key_ladder_path),
+            ("move_numbers", "Move numbers", &mut self.key_move_numbers),
+            ("move_number_range", "Move number range", &mut self.key_move_number_range),
+            ("coordinates", "Coordinates", &mut self.key_coordinates),
+            ("stone_shape", "Stone shape", &mut self.key_stone_shape),
+            ("theme", "Theme", &mut self.key_theme),
+            ("environment", "Environment", &mut self.key_environment),
+            ("view_top_down", "Top-down view", &mut self.key_view_top_down),
+            ("view_black_seat", "Black's seat", &mut self.key_view_black_seat),
+            ("view_white_seat", "White's seat", &mut self.key_view_white_seat),
+            ("view_side", "Side view", &mut self.key_view_side),
+            ("view_corner", "Corner view", &mut self.key_view_corner),
+            ("diagram", "Diagram view", &mut self.key_diagram),
+        ]
+    }
+
+    pub fn bindings(&self) -> [(&'static str, &'static str, KeyCode); 32] {
+        let mut copy = self.clone();
+        copy.bindings_mut().map(|(name, label, key)| (name, label, *key))
+    }
+
+    // the label of the action already bound to `key`, other than the binding `name`
+    pub fn conflict(&self, name: &str, key: KeyCode) -> Option<&'static str> {
+        self.bindings().into_iter()
+            .find(|(other, _, bound)| *other != name && *bound == key)
+            .map(|(_, label, _)| label)
+    }
+}
+
 // maintains per-frame state of mapped activations from input to game-logic actions
 #[derive(Debug, Default, Resource)]
 pub struct KeyInputState {
-    pub forward: bool,
-    pub backward: bool,
-    pub left: bool,
-    pub right: bool,
-    pub run: bool,
-    pub up: bool,
-    pub down: bool,
-    pub jump: bool,
-    pub toggle_fly: bool,
-    pub toggle_view: bool,
+    pub toggle_assist: bool,
+    pub toggle_liberties: bool,
+    pub toggle_ladder_path: bool,
+    pub toggle_move_numbers: bool,
+    pub cycle_move_number_range: bool,
+    pub cycle_coordinates: bool,
+    pub toggle_stone_shape: bool,
+    pub cycle_theme: bool,
+    pub cycle_environment: bool,
+    pub problem_next: bool,
+    pub problem_previous: bool,
+    pub problem_retry: bool,
+    pub review_step: Option<TreeStep>,
+    pub camera_preset: Option<CameraPreset>,
+    pub toggle_diagram: bool,
+    // steps of the keyboard cursor on the board, x to the right and y up the screen
+    pub cursor_step: IVec2,
+    // plays the point under the board cursor
+    pub play_move: bool,
 }
 
 // Plugin for keyboard input systems
@@ -57,37 +171,87 @@ impl Plugin for KeyInputPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<KeyInputMap>();
         app.init_resource::<KeyInputState>();
+        app.add_startup_system(load_keymap);
         app.add_system_set(
             SystemSet::on_update(GameState::Running)
-            .with_system(input_to_move)
+            .with_system(input_to_move.after(gamepad_to_actions))
         );
     }
 }
 
-// updates desired move velocity from keyboard input
+// updates desired move velocity from keyboard input, the board cursor also moves with the gamepads,
+// pausing, passing and undoing are sent as game actions
 pub fn input_to_move(
     keyboard_input: Res<Input<KeyCode>>,
     input_map: Res<KeyInputMap>,
+    game_mode: Res<GameMode>,
     mut state: ResMut<KeyInputState>,
-    cursor_lock: Res<CursorLockState>,
-    mut game_state: ResMut<State<GameState>>,
+    gamepad_state: Res<GamepadInputState>,
+    mut actions: EventWriter<GameAction>,
 ) {
     // check esc
-    if cursor_lock.enabled && keyboard_input.just_pressed(input_map.key_escape) {
-        game_state.set(GameState::Paused).unwrap();
+    if keyboard_input.just_pressed(input_map.key_escape) {
+        actions.send(GameAction::Pause);
+    }
+    if keyboard_input.just_pressed(input_map.key_pass) {
+        actions.send(GameAction::Pass);
+    }
+    if keyboard_input.just_pressed(input_map.key_undo) {
+        actions.send(GameAction::Undo);
     }
 
-    // update input state from key states
-    state.run = cursor_lock.enabled && keyboard_input.pressed(input_map.key_run);
-    state.toggle_fly = cursor_lock.enabled && keyboard_input.just_pressed(input_map.key_fly);
-    state.toggle_view = cursor_lock.enabled && keyboard_input.just_pressed(input_map.key_toggleview);
-    state.jump = cursor_lock.enabled && keyboard_input.just_pressed(input_map.key_jump);
+    // board toggles do not need the cursor locked
+    state.toggle_assist = keyboard_input.just_pressed(input_map.key_assist);
+    state.toggle_liberties = keyboard_input.just_pressed(input_map.key_liberties);
+    state.toggle_ladder_path = keyboard_input.just_pressed(input_map.key_ladder_path);
+    state.toggle_move_numbers = keyboard_input.just_pressed(input_map.key_move_numbers);
+    state.cycle_move_number_range = keyboard_input.just_pressed(input_map.key_move_number_range);
+    state.cycle_coordinates = keyboard_input.just_pressed(input_map.key_coordinates);
+    state.toggle_stone_shape = keyboard_input.just_pressed(input_map.key_stone_shape);
+    state.cycle_theme = keyboard_input.just_pressed(input_map.key_theme);
+    state.cycle_environment = keyboard_input.just_pressed(input_map.key_environment);
+    state.toggle_diagram = keyboard_input.just_pressed(input_map.key_diagram);
+    state.play_move = keyboard_input.just_pressed(input_map.key_play) || gamepad_state.play_move;
+    state.problem_next = keyboard_input.just_pressed(input_map.key_problem_next);
+    state.problem_previous = keyboard_input.just_pressed(input_map.key_problem_previous);
+    state.problem_retry = keyboard_input.just_pressed(input_map.key_problem_retry);
+    state.review_step = [
+        (input_map.key_review_back, TreeStep::Back),
+        (input_map.key_review_forward, TreeStep::Forward),
+        (input_map.key_review_start, TreeStep::Start),
+        (input_map.key_review_end, TreeStep::End),
+        (input_map.key_branch_next, TreeStep::NextBranch),
+        (input_map.key_branch_previous, TreeStep::PreviousBranch),
+    ].iter().find(|(key, _)| keyboard_input.just_pressed(*key)).map(|(_, step)| *step);
+    state.camera_preset = [
+        (input_map.key_view_top_down, CameraPreset::TopDown),
+        (input_map.key_view_black_seat, CameraPreset::Seat(0)),
+        (input_map.key_view_white_seat, CameraPreset::Seat(1)),
+        (input_map.key_view_side, CameraPreset::Side),
+        (input_map.key_view_corner, CameraPreset::Corner),
+    ].iter().find(|(key, _)| keyboard_input.just_pressed(*key)).map(|(_, preset)| *preset);
 
-    // update desired velocity from key states
-    state.forward = cursor_lock.enabled && keyboard_input.pressed(input_map.key_forward);
-    state.backward = cursor_lock.enabled && keyboard_input.pressed(input_map.key_backward);
-    state.right = cursor_lock.enabled && keyboard_input.pressed(input_map.key_right);
-    state.left = cursor_lock.enabled && keyboard_input.pressed(input_map.key_left);
-    state.up = cursor_lock.enabled && keyboard_input.pressed(input_map.key_jump);
-    state.down = cursor_lock.enabled && keyboard_input.pressed(input_map.key_crouch);
+    // the movement keys step the board cursor
+    state.cursor_step = gamepad_state.cursor_step;
+    for (key, step) in [
+        (input_map.key_forward, IVec2::Y),
+        (input_map.key_backward, IVec2::NEG_Y),
+        (input_map.key_right, IVec2::X),
+        (input_map.key_left, IVec2::NEG_X),
+    ] {
+        if keyboard_input.just_pressed(key) {
+            state.cursor_step += step;
+        }
+    }
+    // free play and joseki keep the arrows for the review keys, arrows bound to anything else stay theirs
+    if !matches!(*game_mode, GameMode::Free | GameMode::Joseki) {
+        let bindings = input_map.bindings();
+        for (key, step) in CURSOR_ARROWS {
+            let taken = bindings.iter().any(|(name, _, bound)| *bound == key
+                && !name.starts_with("review_") && !name.starts_with("branch_"));
+            if !taken && keyboard_input.just_pressed(key) {
+                state.cursor_step += step;
+            }
+        }
+    }
 }
@@ -0,0 +1,146 @@
+use crate::actions::key_input::KeyInputMap;
+use crate::persistence::{load_data_file, save_data_file};
+use bevy::prelude::*;
+use std::fmt;
+
+// the keymap is kept between runs in this file
+const KEYMAP_FILE: &str = "keymap.txt";
+
+// keys that can be bound, named as in the keymap file
+const BINDABLE_KEYS: [KeyCode; 96] = [
+    KeyCode::A, KeyCode::B, KeyCode::C, KeyCode::D, KeyCode::E, KeyCode::F, KeyCode::G, KeyCode::H,
+    KeyCode::I, KeyCode::J, KeyCode::K, KeyCode::L, KeyCode::M, KeyCode::N, KeyCode::O, KeyCode::P,
+    KeyCode::Q, KeyCode::R, KeyCode::S, KeyCode::T, KeyCode::U, KeyCode::V, KeyCode::W, KeyCode::X,
+    KeyCode::Y, KeyCode::Z,
+    KeyCode::Key0, KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4,
+    KeyCode::Key5, KeyCode::Key6, KeyCode::Key7, KeyCode::Key8, KeyCode::Key9,
+    KeyCode::F1, KeyCode::F2, KeyCode::F3, KeyCode::F4, KeyCode::F5, KeyCode::F6,
+    KeyCode::F7, KeyCode::F8, KeyCode::F9, KeyCode::F10, KeyCode::F11, KeyCode::F12,
+    KeyCode::Up, KeyCode::Down, KeyCode::Left, KeyCode::Right,
+    KeyCode::Escape, KeyCode::Tab, KeyCode::Return, KeyCode::Space, KeyCode::Back,
+    KeyCode::Insert, KeyCode::Delete, KeyCode::Home, KeyCode::End, KeyCode::PageUp, KeyCode::PageDown,
+    KeyCode::LShift, KeyCode::RShift, KeyCode::LControl, KeyCode::RControl, KeyCode::LAlt, KeyCode::RAlt,
+    KeyCode::Minus, KeyCode::Equals, KeyCode::LBracket, KeyCode::RBracket, KeyCode::Backslash,
+    KeyCode::Semicolon, KeyCode::Apostrophe, KeyCode::Grave, KeyCode::Comma, KeyCode::Period, KeyCode::Slash,
+    KeyCode::Numpad0, KeyCode::Numpad1, KeyCode::Numpad2, KeyCode::Numpad3, KeyCode::Numpad4,
+    KeyCode::Numpad5, KeyCode::Numpad6, KeyCode::Numpad7, KeyCode::Numpad8, KeyCode::Numpad9,
+    KeyCode::NumpadAdd, KeyCode::NumpadSubtract, KeyCode::NumpadMultiply, KeyCode::NumpadDivide,
+    KeyCode::NumpadDecimal, KeyCode::NumpadEnter,
+];
+
+pub fn is_bindable(key: KeyCode) -> bool {
+    BINDABLE_KEYS.contains(&key)
+}
+
+pub fn key_name(key: KeyCode) -> String {
+    format!("{:?}", key)
+}
+
+fn parse_key(name: &str) -> Option<KeyCode> {
+    BINDABLE_KEYS.iter().find(|key| key_name(**key) == name).copied()
+}
+
+#[derive(Debug)]
+pub enum KeymapError {
+    BadLine(usize),
+    UnknownAction(usize, String),
+    UnknownKey(usize, String),
+    Conflict(String, String),
+}
+
+impl fmt::Display for KeymapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeymapError::BadLine(line) => write!(f, "expected `action = key` on line {}", line),
+            KeymapError::UnknownAction(line, action) => write!(f, "unknown action '{}' on line {}", action, line),
+            KeymapError::UnknownKey(line, key) => write!(f, "unknown key '{}' on line {}", key, line),
+            KeymapError::Conflict(first, second) => write!(f, "'{}' and '{}' share a key", first, second),
+        }
+    }
+}
+
+impl std::error::Error for KeymapError {}
+
+// `action = key` lines, `#` starts a comment, actions that are left out keep their default key
+pub fn parse_keymap(text: &str) -> Result<KeyInputMap, KeymapError> {
+    let mut keymap = KeyInputMap::default();
+    for (index, line) in text.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let Some((action, key)) = line.split_once('=') else {
+            return Err(KeymapError::BadLine(line_number));
+        };
+        let (action, key) = (action.trim(), key.trim());
+        let key = parse_key(key).ok_or_else(|| KeymapError::UnknownKey(line_number, key.to_string()))?;
+        let mut bindings = keymap.bindings_mut();
+        let Some((_, _, bound)) = bindings.iter_mut().find(|(name, _, _)| *name == action) else {
+            return Err(KeymapError::UnknownAction(line_number, action.to_string()));
+        };
+        **bound = key;
+    }
+    for (name, label, key) in keymap.bindings() {
+        if let Some(other) = keymap.conflict(name, key) {
+            return Err(KeymapError::Conflict(label.to_string(), other.to_string()));
+        }
+    }
+    Ok(keymap)
+}
+
+pub fn keymap_to_string(keymap: &KeyInputMap) -> String {
+    keymap.bindings().iter()
+        .map(|(name, _, key)| format!("{} = {}\n", name, key_name(*key)))
+        .collect()
+}
+
+pub fn save_keymap(keymap: &KeyInputMap) {
+    if let Err(err) = save_data_file(KEYMAP_FILE, &keymap_to_string(keymap)) {
+        warn!("Could not save keymap: {}", err);
+    }
+}
+
+// a saved keymap replaces the defaults, one that can not be read is left alone
+pub fn load_keymap(mut keymap: ResMut<KeyInputMap>) {
+    let Some(text) = load_data_file(KEYMAP_FILE) else {
+        return;
+    };
+    match parse_keymap(&text) {
+        Ok(loaded) => *keymap = loaded,
+        Err(err) => warn!("Could not load keymap, using the default keys: {}", err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keymap_round_trips() {
+        let keymap = KeyInputMap { key_pass: KeyCode::Q, key_diagram: KeyCode::Numpad5, ..default() };
+        let parsed = parse_keymap(&keymap_to_string(&keymap)).unwrap();
+        assert_eq!(parsed.bindings(), keymap.bindings());
+    }
+
+    #[test]
+    fn comments_blank_lines_and_left_out_actions_keep_defaults() {
+        let parsed = parse_keymap("# my keys\n\npass = Q # was P\n").unwrap();
+        assert_eq!(parsed.key_pass, KeyCode::Q);
+        assert_eq!(parsed.key_undo, KeyInputMap::default().key_undo);
+    }
+
+    #[test]
+    fn conflicting_keys_are_rejected() {
+        // P is the default pass key
+        assert!(matches!(parse_keymap("undo = P"), Err(KeymapError::Conflict(_, _))));
+        assert!(parse_keymap("undo = P\npass = Q").is_ok());
+    }
+
+    #[test]
+    fn unknown_actions_and_keys_are_rejected() {
+        assert!(matches!(parse_keymap("pass = Q\njump = Space"), Err(KeymapError::UnknownAction(2, action)) if action == "jump"));
+        assert!(matches!(parse_keymap("pass = Hyper"), Err(KeymapError::UnknownKey(1, key)) if key == "Hyper"));
+        assert!(matches!(parse_keymap("pass Q"), Err(KeymapError::BadLine(1))));
+    }
+}
@@ -1,19 +1,32 @@
 use bevy::prelude::*;
 
+mod game_action;
 mod key_input;
+mod keymap;
 mod mouse_input;
 mod camera_movement;
+mod gamepad_input;
+mod touch_input;
 
 pub struct ActionsPlugin;
+pub use camera_movement::{CamerasState, FrameCameraEvent};
+pub use game_action::GameAction;
+pub use gamepad_input::gamepad_to_actions;
+pub use key_input::{KeyInputMap, KeyInputState, input_to_move};
+pub use keymap::{is_bindable, key_name, save_keymap};
 pub use mouse_input::{CursorLockState,MouseCamera,MouseLookState};
+pub use touch_input::{TouchInputState, TouchTap, touch_to_actions};
 
 // This plugin listens for keyboard input and converts the input into Actions
 // Actions can then be used as a resource in other systems to act on the player input.
 impl Plugin for ActionsPlugin {
     fn build(&self, app: &mut App) {
         app
+            .add_plugin(game_action::GameActionPlugin)
             .add_plugin(key_input::KeyInputPlugin)
             .add_plugin(mouse_input::MouseInputPlugin)
+            .add_plugin(gamepad_input::GamepadInputPlugin)
+            .add_plugin(touch_input::TouchInputPlugin)
             .add_plugin(camera_movement::CamerasStatePlugin)
             ;
     }
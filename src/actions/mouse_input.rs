@@ -1,6 +1,8 @@
-use bevy::{prelude::*, input::mouse::{MouseMotion,MouseWheel}, window::CursorGrabMode};
+use bevy::{prelude::*, input::mouse::{MouseMotion,MouseWheel}};
 #[cfg(target_arch = "wasm32")]
-use web_sys;
+use bevy::window::CursorGrabMode;
+#[cfg(target_arch = "wasm32")]
+use crate::actions::game_action::GameAction;
 use crate::game_state::GameState;
 
 #[derive(Resource)]
@@ -18,7 +20,8 @@ impl Default for MouseSettings {
     }
 }
 
-const PITCH_BOUND: f32 = std::f32::consts::FRAC_PI_2 - 1E-3;
+// the orbit camera stays above the board, between a low view and looking straight down
+pub const ORBIT_PITCH_BOUNDS: (f32, f32) = (-std::f32::consts::FRAC_PI_2 + 1E-3, -0.15);
 
 #[derive(Default, Resource)]
 pub struct CursorLockState {
@@ -46,6 +49,27 @@ impl Default for MouseLookState {
     }
 }
 
+impl MouseLookState {
+    // recomputes the view vectors after yaw_pitch_roll changed
+    pub fn update_vectors(&mut self) {
+        let x_rotation = Quat::from_euler(
+            EulerRot::XYZ,
+            0.0,
+            self.yaw_pitch_roll.x,
+            0.0,
+        );
+        let y_rotation = Quat::from_euler(
+            EulerRot::XYZ,
+            self.yaw_pitch_roll.y,
+            0.0, 0.0
+        );
+        let rotation = x_rotation * y_rotation;
+        self.forward = rotation * -Vec3::Z;
+        self.right = rotation * Vec3::X;
+        self.up = rotation * Vec3::Y;
+    }
+}
+
 #[derive(Clone, Copy, Component, Default)]
 pub struct MouseCamera {
 }
@@ -63,7 +87,6 @@ impl Plugin for MouseInputPlugin {
             .with_system(setup_mouse_inputs))
         .add_system_set(
             SystemSet::on_update(GameState::Running)
-            .with_system(update_cursor_lock)
             .with_system(update_cursor_lock_wasm_running)
             .with_system(input_to_look)
         )
@@ -77,18 +100,18 @@ impl Plugin for MouseInputPlugin {
 #[cfg(target_arch = "wasm32")]
 pub fn update_cursor_lock_wasm_running(
     mut cursor_lock_controls: ResMut<CursorLockState>,
-    mut state: ResMut<State<GameState>>,
+    mut actions: EventWriter<GameAction>,
     mut windows: ResMut<Windows>,
 ) {
     let browser_window = web_sys::window().expect("could not get window handle");
     let document = browser_window.document().expect("could not get document handle");
     // check for silent cursor lock exit (browser)
-    if cursor_lock_controls.enabled && !document.pointer_lock_element().is_some() {
+    if cursor_lock_controls.enabled && document.pointer_lock_element().is_none() {
         let window = windows.get_primary_mut().unwrap();
-        window.set_cursor_lock_mode(false);
+        window.set_cursor_grab_mode(CursorGrabMode::None);
         window.set_cursor_visibility(true);
         cursor_lock_controls.enabled = false;
-        state.set(GameState::Paused).unwrap();
+        actions.send(GameAction::Pause);
     }
 }
 #[cfg(target_arch = "wasm32")]
@@ -102,10 +125,10 @@ pub fn update_cursor_lock_wasm_paused(
     // check for cursor lock grab during paused, implying resume clicked
     if document.pointer_lock_element().is_some() {
         let window = windows.get_primary_mut().unwrap();
-        window.set_cursor_lock_mode(true);
+        window.set_cursor_grab_mode(CursorGrabMode::Locked);
         window.set_cursor_visibility(false);
         cursor_lock_controls.enabled = true;
-        state.set(GameState::Running).unwrap();
+        state.pop().unwrap();
     }
 }
 
@@ -124,43 +147,17 @@ fn setup_mouse_inputs(
     for _ in mouse_wheel_events.iter() { }
 
     // init vectors from ypr
-    let x_rotation = Quat::from_euler(
-        EulerRot::XYZ,
-        0.0,
-        mouse_look.yaw_pitch_roll.x,
-        0.0,
-    );
-    let y_rotation = Quat::from_euler(
-        EulerRot::XYZ,
-        mouse_look.yaw_pitch_roll.y,
-        0.0, 0.0
-    );
-    let rotation = x_rotation * y_rotation;
-    mouse_look.forward = rotation * -Vec3::Z;
-    mouse_look.right = rotation * Vec3::X;
-    mouse_look.up = rotation * Vec3::Y;
-}
-
-pub fn update_cursor_lock(
-    mouse_btn_input: Res<Input<MouseButton>>,
-    mut cursor_lock_controls: ResMut<CursorLockState>,
-    mut windows: ResMut<Windows>,
-) {
-    let window = windows.get_primary_mut().unwrap();
-    // check for click to enter cursor lock
-    // if mouse_btn_input.pressed(MouseButton::Left) {
-    //     window.set_cursor_grab_mode(CursorGrabMode::Locked);
-    //     window.set_cursor_visibility(false);
-    //     cursor_lock_controls.enabled = true;
-    // }
+    mouse_look.update_vectors();
 }
 
+// orbits the board while the right button is held, the wheel zooms unless it is over the ui
 pub fn input_to_look(
     mut mouse_motion_events: EventReader<MouseMotion>,
     mut mouse_wheel_events: EventReader<MouseWheel>,
     mut mouse_look: ResMut<MouseLookState>,
     settings: Res<MouseSettings>,
-    cursor_lock: Res<CursorLockState>,
+    mouse_btn_input: Res<Input<MouseButton>>,
+    interaction_query: Query<&Interaction>,
 ) {
     let mut delta = Vec2::ZERO;
     for motion in mouse_motion_events.iter() {
@@ -170,34 +167,17 @@ pub fn input_to_look(
     for wheel_motion in mouse_wheel_events.iter() {
         wheel_delta_y -= wheel_motion.y;
     }
-    if !cursor_lock.enabled {
+    let over_ui = interaction_query.iter().any(|interaction| *interaction != Interaction::None);
+    if !over_ui && wheel_delta_y != 0.0 {
+        mouse_look.zoom += wheel_delta_y * settings.zoom_sensitivity;
+    }
+    if !mouse_btn_input.pressed(MouseButton::Right) {
         return;
     }
     if delta.length_squared() > 1E-6 {
         delta *= settings.sensitivity;
         mouse_look.yaw_pitch_roll += delta.extend(0.0);
-        if mouse_look.yaw_pitch_roll.y > PITCH_BOUND {
-            mouse_look.yaw_pitch_roll.y = PITCH_BOUND;
-        }
-        if mouse_look.yaw_pitch_roll.y < -PITCH_BOUND {
-            mouse_look.yaw_pitch_roll.y = -PITCH_BOUND;
-        }
-
-        let x_rotation = Quat::from_euler(
-            EulerRot::XYZ,
-            0.0,
-            mouse_look.yaw_pitch_roll.x,
-            0.0,
-        );
-        let y_rotation = Quat::from_euler(
-            EulerRot::XYZ,
-            mouse_look.yaw_pitch_roll.y,
-            0.0, 0.0
-        );
-        let rotation = x_rotation * y_rotation;
-        mouse_look.forward = rotation * -Vec3::Z;
-        mouse_look.right = rotation * Vec3::X;
-        mouse_look.up = rotation * Vec3::Y;
+        mouse_look.yaw_pitch_roll.y = mouse_look.yaw_pitch_roll.y.clamp(ORBIT_PITCH_BOUNDS.0, ORBIT_PITCH_BOUNDS.1);
+        mouse_look.update_vectors();
     }
-    mouse_look.zoom += wheel_delta_y * settings.zoom_sensitivity;
 }
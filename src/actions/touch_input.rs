@@ -0,0 +1,164 @@
+use crate::actions::camera_movement::CamerasState;
+use crate::actions::MouseLookState;
+use crate::actions::mouse_input::{MouseSettings, ORBIT_PITCH_BOUNDS};
+use crate::game_state::GameState;
+use bevy::{prelude::*, input::touch::Touch};
+#[cfg(target_arch = "wasm32")]
+use bevy::input::touch::TouchPhase;
+#[cfg(target_arch = "wasm32")]
+use std::sync::{Arc, Mutex};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::{closure::Closure, JsCast};
+
+// how far a finger may move and still tap, in pixels
+const TAP_SLOP: f32 = 12.;
+// browsers follow a tap with mouse events of their own, those are ignored for this many seconds
+const TOUCH_MOUSE_GRACE: f32 = 0.6;
+// two-finger drags turn the camera faster than the mouse, fingers cover fewer pixels
+const TOUCH_ORBIT_SCALE: f32 = 2.5;
+
+// a finger put down and lifted again without moving, at a cursor position with the origin at the bottom left
+pub struct TouchTap {
+    pub position: Vec2,
+}
+
+// touch never asks for the pointer lock, the board is played by tapping and the camera moved with two fingers
+#[derive(Default, Resource)]
+pub struct TouchInputState {
+    // seconds left in which mouse events are the browser's copy of touches
+    mouse_grace: f32,
+    // the fingers down moved or were joined by another, lifting them is no tap
+    gesture: bool,
+}
+
+impl TouchInputState {
+    pub fn emulating_mouse(&self) -> bool {
+        self.mouse_grace > 0.
+    }
+}
+
+// touches read from the canvas, winit only passes them on as mouse events in the browser
+#[cfg(target_arch = "wasm32")]
+#[derive(Default, Resource)]
+struct WebTouches {
+    queue: Arc<Mutex<Vec<TouchInput>>>,
+}
+
+pub struct TouchInputPlugin;
+
+impl Plugin for TouchInputPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TouchInputState>();
+        app.add_event::<TouchTap>();
+        app.add_system_set(
+            SystemSet::on_update(GameState::Running)
+            .with_system(touch_to_actions)
+        );
+        #[cfg(target_arch = "wasm32")]
+        app.init_resource::<WebTouches>()
+            .add_startup_system(listen_web_touches)
+            // before bevy reads the touch events into `Touches`
+            .add_system_to_stage(CoreStage::First, forward_web_touches);
+    }
+}
+
+// taps go to the board, pinching zooms and dragging two fingers orbits the camera
+#[allow(clippy::too_many_arguments)]
+pub fn touch_to_actions(
+    time: Res<Time>,
+    touches: Res<Touches>,
+    windows: Res<Windows>,
+    settings: Res<MouseSettings>,
+    mut state: ResMut<TouchInputState>,
+    mut cameras_state: ResMut<CamerasState>,
+    mut mouse_look: ResMut<MouseLookState>,
+    mut tap_events: EventWriter<TouchTap>,
+) {
+    let down: Vec<&Touch> = touches.iter().collect();
+    if !down.is_empty() || touches.iter_just_released().next().is_some() {
+        state.mouse_grace = TOUCH_MOUSE_GRACE;
+    } else if state.mouse_grace > 0. {
+        state.mouse_grace -= time.delta_seconds();
+    }
+
+    if let [first, second, ..] = down[..] {
+        state.gesture = true;
+        // spreading the fingers brings the camera closer, zoom is logarithmic like the pinch
+        let distance = first.position().distance(second.position());
+        let previous = first.previous_position().distance(second.previous_position());
+        if distance > 0. && previous > 0. && distance != previous {
+            mouse_look.zoom -= (distance / previous).ln();
+        }
+        let drag = (first.delta() + second.delta()) / 2.;
+        if drag != Vec2::ZERO {
+            // turning by hand leaves the camera preset
+            cameras_state.preset = None;
+            cameras_state.transition = None;
+            let turn = -drag * settings.sensitivity * TOUCH_ORBIT_SCALE;
+            mouse_look.yaw_pitch_roll.x += turn.x;
+            mouse_look.yaw_pitch_roll.y = (mouse_look.yaw_pitch_roll.y + turn.y).clamp(ORBIT_PITCH_BOUNDS.0, ORBIT_PITCH_BOUNDS.1);
+            mouse_look.update_vectors();
+        }
+    } else if let [finger] = down[..] {
+        if finger.distance().length() > TAP_SLOP {
+            state.gesture = true;
+        }
+    }
+
+    for touch in touches.iter_just_released() {
+        if !state.gesture && down.is_empty() && touch.distance().length() <= TAP_SLOP {
+            // touches start at the top left, the cursor at the bottom left
+            let height = windows.get_primary().map_or(0., |window| window.height());
+            tap_events.send(TouchTap { position: Vec2::new(touch.position().x, height - touch.position().y) });
+        }
+    }
+    if down.is_empty() {
+        state.gesture = false;
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn listen_web_touches(web_touches: Res<WebTouches>) {
+    let Some(canvas) = web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.query_selector("#bevy").ok().flatten()) else {
+        return;
+    };
+    for (event_name, phase) in [
+        ("touchstart", TouchPhase::Started),
+        ("touchmove", TouchPhase::Moved),
+        ("touchend", TouchPhase::Ended),
+        ("touchcancel", TouchPhase::Cancelled),
+    ] {
+        let queue = web_touches.queue.clone();
+        let target = canvas.clone();
+        let listener = Closure::<dyn FnMut(web_sys::TouchEvent)>::new(move |event: web_sys::TouchEvent| {
+            // css pixels from the top left of the canvas, as winit reports touches elsewhere
+            let rect = target.get_bounding_client_rect();
+            let changed = event.changed_touches();
+            let mut queue = queue.lock().unwrap();
+            for touch in (0..changed.length()).filter_map(|index| changed.get(index)) {
+                queue.push(TouchInput {
+                    phase,
+                    position: Vec2::new(
+                        (touch.client_x() as f64 - rect.left()) as f32,
+                        (touch.client_y() as f64 - rect.top()) as f32,
+                    ),
+                    force: None,
+                    id: touch.identifier() as u64,
+                });
+            }
+        });
+        let _ = canvas.add_event_listener_with_callback(event_name, listener.as_ref().unchecked_ref());
+        // the listeners stay for as long as the page
+        listener.forget();
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn forward_web_touches(
+    web_touches: Res<WebTouches>,
+    mut touch_events: EventWriter<TouchInput>,
+) {
+    touch_events.send_batch(web_touches.queue.lock().unwrap().drain(..));
+}
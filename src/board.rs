@@ -1,4 +1,30 @@
+mod assist;
+pub use assist::*;
 mod board_state;
 pub use board_state::*;
+mod bowls;
+pub use bowls::*;
+mod coordinate_labels;
+pub use coordinate_labels::*;
+mod cube_env;
+pub use cube_env::*;
+mod goban;
+pub use goban::*;
+mod stone_animation;
+pub use stone_animation::*;
+mod stone_materials;
+pub use stone_materials::*;
+mod stone_mesh;
+pub use stone_mesh::*;
 mod game_board;
 pub use game_board::*;
+mod ladder;
+pub use ladder::*;
+mod move_numbers;
+pub use move_numbers::*;
+mod sgf;
+pub use sgf::*;
+mod theme;
+pub use theme::*;
+mod world_labels;
+pub use world_labels::*;
@@ -0,0 +1,177 @@
+use crate::actions::KeyInputState;
+use crate::board::{BoardState, GameBoard, StoneMeshes, apply_board_effects, read_ladder, space_to_world, spawn_world_label, SPACING};
+use crate::game_state::GameState;
+use crate::loading::PreLoadingState;
+use bevy::prelude::*;
+
+// optional beginner aids drawn over the board
+#[derive(Default, Resource)]
+pub struct AssistState {
+    pub enabled: bool,
+    pub show_liberties: bool,
+    pub show_ladder_path: bool,
+    ring_mesh: Handle<Mesh>,
+    atari_material: Handle<StandardMaterial>,
+    ladder_material: Handle<StandardMaterial>,
+    path_mesh: Handle<Mesh>,
+    path_materials: [Handle<StandardMaterial>; 2],
+}
+
+#[derive(Component)]
+pub struct AssistMarker;
+
+pub struct AssistPlugin;
+
+impl Plugin for AssistPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<AssistState>()
+            .add_startup_system(setup_assist)
+            .add_system_set(SystemSet::on_update(GameState::Running)
+                .with_system(update_assist_toggles)
+                .with_system(update_assist_markers.after(update_assist_toggles).after(apply_board_effects)))
+            ;
+    }
+}
+
+fn setup_assist(
+    mut assist: ResMut<AssistState>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    assist.ring_mesh = meshes.add(Mesh::from(shape::Torus {
+        radius: 0.0082,
+        ring_radius: 0.0008,
+        ..default()
+    }));
+    assist.atari_material = materials.add(StandardMaterial {
+        base_color: Color::rgb(0.9, 0.1, 0.1),
+        unlit: true,
+        ..default()
+    });
+    assist.ladder_material = materials.add(StandardMaterial {
+        base_color: Color::rgb(1.0, 0.6, 0.0),
+        unlit: true,
+        ..default()
+    });
+    assist.path_mesh = meshes.add(Mesh::from(shape::Icosphere {
+        radius: 0.003,
+        ..default()
+    }));
+    assist.path_materials = [
+        materials.add(StandardMaterial {
+            base_color: Color::rgba(0.1, 0.1, 0.1, 0.6),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        }),
+        materials.add(StandardMaterial {
+            base_color: Color::rgba(1.0, 1.0, 1.0, 0.6),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        }),
+    ];
+}
+
+fn update_assist_toggles(
+    key_state: Res<KeyInputState>,
+    mut assist: ResMut<AssistState>,
+) {
+    if key_state.toggle_assist {
+        assist.enabled = !assist.enabled;
+    }
+    if key_state.toggle_liberties {
+        assist.show_liberties = !assist.show_liberties;
+    }
+    if key_state.toggle_ladder_path {
+        assist.show_ladder_path = !assist.show_ladder_path;
+    }
+}
+
+// rebuilds atari rings, ladder warnings and liberty counts whenever the board, the player to move
+// or the assist options change, ladders are only read for the player to move
+#[allow(clippy::too_many_arguments)]
+fn update_assist_markers(
+    mut commands: Commands,
+    assist: Res<AssistState>,
+    game_board: Res<GameBoard>,
+    board_state: Res<BoardState>,
+    stone_meshes: Res<StoneMeshes>,
+    pre_loading_state: Res<PreLoadingState>,
+    markers_query: Query<Entity, With<AssistMarker>>,
+    mut drawn_turn: Local<usize>,
+) {
+    if !assist.is_changed() && !game_board.is_changed() && !stone_meshes.is_changed() && *drawn_turn == board_state.player_turn {
+        return;
+    }
+    *drawn_turn = board_state.player_turn;
+    for ent in markers_query.iter() {
+        commands.entity(ent).despawn_recursive();
+    }
+    if !assist.enabled {
+        return;
+    }
+
+    // rings and path markers sit at the rim of the stones
+    let rim = Vec3::Y * stone_meshes.shape.bottom;
+    for group in game_board.groups() {
+        if group.is_atari() {
+            // a group of the player to move that cannot run out of a ladder
+            let ladder = if group.player == board_state.player_turn {
+                read_ladder(&game_board, group.spaces[0]).filter(|reading| reading.captured)
+            } else {
+                None
+            };
+
+            for space in group.spaces.iter() {
+                commands.spawn(PbrBundle {
+                    mesh: assist.ring_mesh.clone(),
+                    material: if ladder.is_some() { assist.ladder_material.clone() } else { assist.atari_material.clone() },
+                    transform: Transform::from_translation(space_to_world(*space, game_board.size) + rim),
+                    ..default()
+                }).insert(AssistMarker);
+            }
+
+            if let Some(reading) = ladder {
+                let label_ent = spawn_world_label(
+                    &mut commands,
+                    space_to_world(group.spaces[0], game_board.size) + Vec3::Y * SPACING * 1.5,
+                    "Ladder".to_string(),
+                    TextStyle {
+                        font: pre_loading_state.font_handle.clone(),
+                        font_size: 18.0,
+                        color: Color::rgb(1.0, 0.6, 0.0),
+                    },
+                );
+                commands.entity(label_ent).insert(AssistMarker);
+
+                if assist.show_ladder_path {
+                    for path_move in reading.path.iter() {
+                        commands.spawn(PbrBundle {
+                            mesh: assist.path_mesh.clone(),
+                            material: assist.path_materials[path_move.player].clone(),
+                            transform: Transform::from_translation(space_to_world(path_move.space, game_board.size) + rim),
+                            ..default()
+                        }).insert(AssistMarker);
+                    }
+                }
+            }
+        }
+
+        if assist.show_liberties {
+            let label_color = if group.player == 1 { Color::BLACK } else { Color::WHITE };
+            let label_ent = spawn_world_label(
+                &mut commands,
+                space_to_world(group.spaces[0], game_board.size) + Vec3::Y * SPACING * 0.5,
+                group.liberties.len().to_string(),
+                TextStyle {
+                    font: pre_loading_state.font_handle.clone(),
+                    font_size: 18.0,
+                    color: if group.is_atari() { Color::rgb(0.9, 0.1, 0.1) } else { label_color },
+                },
+            );
+            commands.entity(label_ent).insert(AssistMarker);
+        }
+    }
+}
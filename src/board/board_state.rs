@@ -1,50 +1,138 @@
-use crate::actions::MouseCamera;
-use crate::board::game_board::{GameBoard, GameBoardMove, GameBoardEffectType, try_move};
-use crate::game_state::GameState;
+use crate::actions::{GameAction, KeyInputState, MouseCamera, TouchInputState, TouchTap, gamepad_to_actions, input_to_move, touch_to_actions};
+use crate::board::{
+    AssistPlugin, CoordinateLabelsPlugin, CubeEnvPlugin, GobanPlugin, MoveNumbers, MoveNumbersPlugin, StoneAnimationPlugin,
+    StoneCapture, StoneDrop, StoneLod, StoneMaterial, StoneMaterials, StoneMaterialsPlugin, StoneMeshes, StoneMeshPlugin, ThemePlugin,
+    WorldLabelPlugin, BowlsPlugin, Goban, lid_drop_point,
+};
+use crate::board::{SgfPlugin, game_board::{GameBoard, GameBoardEffect, GameBoardMove, GameBoardEffectType, IllegalMove, check_move, pass_move, try_move}};
+use crate::game_state::{GameMode, GameState};
 use crate::loading::PreLoadingState;
 use bevy::prelude::*;
 use bevy_kira_audio::prelude::*;
 use bevy_rapier3d::prelude::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 
-const SPACING: f32 = 0.015;
+pub const SPACING: f32 = 0.015;
+const LAST_MOVE_MARKER_RADIUS: f32 = 0.0035;
+const BOARD_CURSOR_RADIUS: f32 = 0.0065;
 
 pub struct BoardStatePlugin;
 
 #[derive(Default, Resource)]
 pub struct BoardState {
-    player_turn: usize,
+    pub player_turn: usize,
+    // limits play to a rectangle of spaces, min and max inclusive
+    pub play_area: Option<((usize,usize),(usize,usize))>,
+    // ignores board input, for example while a reply is being played
+    pub locked: bool,
+    // the point picked with the keys, a gamepad or a tap, until the mouse is moved again
+    pub board_cursor: Option<(usize,usize)>,
     pause_actions: f32,
-    light_stone: Handle<StandardMaterial>,
-    dark_stone: Handle<StandardMaterial>,
-    stone_mesh: Handle<Mesh>,
+    // game actions that arrived after a move was made that frame, played one a frame
+    pending_actions: VecDeque<GameAction>,
+    light_stone: Handle<StoneMaterial>,
+    dark_stone: Handle<StoneMaterial>,
+    // hover stone tint on points where the move would be rejected
+    illegal_stone: Handle<StoneMaterial>,
     piece_ents: HashMap<(usize,usize),Entity>,
 }
 
 
+impl BoardState {
+    pub fn in_play_area(&self, space: (usize,usize)) -> bool {
+        self.play_area.is_none_or(|(min, max)| {
+            space.0 >= min.0 && space.0 <= max.0 && space.1 >= min.1 && space.1 <= max.1
+        })
+    }
+}
+
 #[derive(Default, Component)]
 pub struct BoardActivePiece {
     player: usize,
 }
 
+// why the rules turned a game action down
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum ActionRejection {
+    // the board is waiting, for a reply or after a problem ended
+    Locked,
+    OutsidePlayArea,
+    Illegal(IllegalMove),
+    // not part of this game mode, such as passing in a problem
+    NotAllowed,
+}
+
+// a game action that was not played, for the input, bot or peer that sent it
+#[derive(Clone, Copy, Debug)]
+pub struct GameActionRejected {
+    pub action: GameAction,
+    pub reason: ActionRejection,
+}
+
+#[derive(Component)]
+pub struct LastMoveMarker;
+
+#[derive(Component)]
+pub struct BoardCursorMarker;
+
 impl Plugin for BoardStatePlugin {
     fn build(&self, app: &mut App) {
         app
             .init_resource::<BoardState>()
             .init_resource::<GameBoard>()
+            .add_event::<GameBoardEffect>()
+            .add_event::<GameActionRejected>()
+            .add_plugin(SgfPlugin)
+            .add_plugin(GobanPlugin)
+            .add_plugin(StoneMeshPlugin)
+            .add_plugin(StoneMaterialsPlugin)
+            .add_plugin(StoneAnimationPlugin)
+            .add_plugin(BowlsPlugin)
+            .add_plugin(ThemePlugin)
+            .add_plugin(CubeEnvPlugin)
+            .add_plugin(AssistPlugin)
+            .add_plugin(MoveNumbersPlugin)
+            .add_plugin(CoordinateLabelsPlugin)
+            .add_plugin(WorldLabelPlugin)
             .add_system_set(SystemSet::on_enter(GameState::Running).with_system(setup_world_loading))
-            .add_system_set(SystemSet::on_update(GameState::Running).with_system(update_board_state))
+            .add_system_set(SystemSet::on_update(GameState::Running).with_system(update_board_state.after(touch_to_actions))
+                .with_system(apply_game_actions.after(update_board_state).after(input_to_move).after(gamepad_to_actions))
+                .with_system(report_rejected_actions.after(apply_game_actions))
+                .with_system(apply_board_effects.after(apply_game_actions))
+                .with_system(update_last_move_marker.after(apply_board_effects))
+                .with_system(update_board_cursor_marker.after(apply_board_effects)))
             ;
     }
 }
 
 
+// world position on the board surface of a point given in lines, which may lie off the grid,
+// the board of `size` lines is centred on the origin
+pub fn grid_to_world(x: f32, z: f32, size: usize) -> Vec3 {
+    let half = (size - 1) as f32 / 2.;
+    SPACING * Vec3::new(x - half, 0., z - half)
+}
+
+// world position of a stone placed on `space`, stone meshes have their origin at the bottom
+pub fn space_to_world(space: (usize,usize), size: usize) -> Vec3 {
+    grid_to_world(space.0 as f32, space.1 as f32, size)
+}
+
+// the intersection nearest to a point on the board surface
+pub fn world_to_space(position: Vec3, size: usize) -> (usize,usize) {
+    let half = (size - 1) as f32 / 2.;
+    let to_line = |v: f32| (v / SPACING + half).round().clamp(0., (size - 1) as f32) as usize;
+    (to_line(position.x), to_line(position.z))
+}
+
 fn setup_world_loading(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
     mut board_state: ResMut<BoardState>,
+    stone_meshes: Res<StoneMeshes>,
+    stone_materials: Res<StoneMaterials>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
+    mut stone_material_assets: ResMut<Assets<StoneMaterial>>,
 ) {
     board_state.pause_actions = 0.2;
 
@@ -59,65 +147,84 @@ fn setup_world_loading(
         ..default()
     });
 
-    board_state.dark_stone = materials.add(StandardMaterial {
-        base_color: Color::hex("20312f").unwrap(),
-        metallic: 0.8,
-        perceptual_roughness: 0.2,
-        ..default()
-    });
-    board_state.light_stone = materials.add(StandardMaterial {
-        base_color: Color::hex("ffffff").unwrap(),
-        metallic: 0.8,
-        perceptual_roughness: 0.2,
-        ..default()
-    });
+    board_state.dark_stone = stone_materials.slate[0].clone();
+    board_state.light_stone = stone_materials.shell[0].clone();
 
-    board_state.stone_mesh = meshes.add(Mesh::from(shape::Icosphere {
-        radius: 0.0072,
+    board_state.illegal_stone = stone_material_assets.add(StoneMaterial {
+        base_color: Color::rgba(0.9, 0.15, 0.15, 0.5),
+        alpha_mode: AlphaMode::Blend,
+        unlit: true,
         ..default()
-    }));
+    });
 
     // active piece
-    commands.spawn(PbrBundle {
-        mesh: board_state.stone_mesh.clone(),
+    commands.spawn(MaterialMeshBundle {
+        mesh: stone_meshes.mesh(),
         material: board_state.dark_stone.clone(),
-        transform: Transform::from_xyz(0.,  -9999., 0.).with_scale(Vec3::new(1.0,0.5,1.0)),
+        transform: Transform::from_xyz(0.,  -9999., 0.),
         ..default()
     }).insert(BoardActivePiece { player: 0 });
-    commands.spawn(PbrBundle {
-        mesh: board_state.stone_mesh.clone(),
+    commands.spawn(MaterialMeshBundle {
+        mesh: stone_meshes.mesh(),
         material: board_state.light_stone.clone(),
-        transform: Transform::from_xyz(0.,  -9999., 0.).with_scale(Vec3::new(1.0,0.5,1.0)),
+        transform: Transform::from_xyz(0.,  -9999., 0.),
         ..default()
     }).insert(BoardActivePiece { player: 1 });
 
-    // board
-    commands.spawn(SceneBundle {
-        scene: asset_server.load("models/goban_2k.glb#Scene0"),
+    // last move marker, a ring lying on top of the stone
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::from(shape::Torus {
+            radius: LAST_MOVE_MARKER_RADIUS,
+            ring_radius: 0.0006,
+            ..default()
+        })),
+        material: materials.add(StandardMaterial {
+            base_color: Color::rgb(0.9, 0.1, 0.1),
+            unlit: true,
+            ..default()
+        }),
+        transform: Transform::from_xyz(0.,  -9999., 0.),
         ..default()
-    });
-    commands.spawn(SpatialBundle {
-        transform: Transform::from_scale(Vec3::new(0.29, 0.01, 0.29)),
+    }).insert(LastMoveMarker);
+
+    // board cursor, a ring around the picked point
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::from(shape::Torus {
+            radius: BOARD_CURSOR_RADIUS,
+            ring_radius: 0.0005,
+            ..default()
+        })),
+        material: materials.add(StandardMaterial {
+            base_color: Color::rgb(0.95, 0.8, 0.1),
+            unlit: true,
+            ..default()
+        }),
+        transform: Transform::from_xyz(0.,  -9999., 0.),
         ..default()
-    })
-    .insert(RigidBody::Fixed)
-    .insert(Collider::cuboid(0.5,0.5,0.5));
+    }).insert(BoardCursorMarker);
 }
 
 
 
+// shows the stone for the point under the mouse or the board cursor and asks to play it,
+// a tap moves the board cursor and a second tap on the same point plays it
+#[allow(clippy::too_many_arguments)]
 fn update_board_state(
-    mut commands: Commands,
     mut board_state: ResMut<BoardState>,
-    mut game_board: ResMut<GameBoard>,
-    preloading: ResMut<PreLoadingState>,
-    audio: Res<Audio>,
+    game_board: Res<GameBoard>,
+    mut actions: EventWriter<GameAction>,
     time: Res<Time>,
+    key_state: Res<KeyInputState>,
+    mut cursor_moved_events: EventReader<CursorMoved>,
+    mut tap_events: EventReader<TouchTap>,
+    touch_state: Res<TouchInputState>,
     windows: Res<Windows>,
     rapier_context: Res<RapierContext>,
-    cameras_query: Query<(&Camera, &GlobalTransform, With<MouseCamera>)>,
-    mut active_piece_query: Query<(&mut Transform, &BoardActivePiece)>,
+    cameras_query: Query<(&Camera, &GlobalTransform), With<MouseCamera>>,
+    mut active_piece_query: Query<(&mut Transform, &mut Handle<StoneMaterial>, &BoardActivePiece)>,
     mouse_btn_input: Res<Input<MouseButton>>,
+    interaction_query: Query<&Interaction>,
+    goban_query: Query<(), With<Goban>>,
 ) {
     // pause actions
     if board_state.pause_actions > 0.0 {
@@ -125,105 +232,307 @@ fn update_board_state(
         return;
     }
 
+    // the mouse the browser makes up for a touch leaves the cursor where it was tapped
+    if cursor_moved_events.iter().last().is_some() && !touch_state.emulating_mouse() {
+        board_state.board_cursor = None;
+    }
+
     // We will color in read the colliders hovered by the mouse.
-    for (camera, camera_transform, _mc) in cameras_query.iter() {
+    for (camera, camera_transform) in cameras_query.iter() {
+        let window = windows.get_primary().unwrap();
+        // clicks on ui buttons do not reach the board
+        let over_ui = interaction_query.iter().any(|interaction| *interaction != Interaction::None);
+        let (locked, size) = (board_state.locked, game_board.size);
+        let pick_space = |screen_position: Vec2| {
+            if locked || over_ui {
+                return None;
+            }
+            let (ray_pos, ray_dir) = ray_from_screen_position(window, screen_position, camera, camera_transform);
+            rapier_context.cast_ray(
+                ray_pos,
+                ray_dir,
+                20.,
+                true,
+                // only the board, not the bowls and lids beside it
+                QueryFilter::only_fixed().predicate(&|entity| goban_query.contains(entity)),
+            ).map(|(_entity, toi)| world_to_space(ray_pos + ray_dir * toi, size))
+        };
+
+        let mut tapped = false;
+        for tap in tap_events.iter() {
+            let space = pick_space(tap.position);
+            tapped = space.is_some() && space == board_state.board_cursor;
+            board_state.board_cursor = space;
+        }
 
-        let (ray_pos, ray_dir) =
-            ray_from_mouse_position(windows.get_primary().unwrap(), camera, camera_transform);
+        if key_state.cursor_step != IVec2::ZERO && !board_state.locked {
+            board_state.board_cursor = Some(match board_state.board_cursor {
+                Some(space) => step_keyboard_cursor(&board_state, space, key_state.cursor_step, camera_transform, game_board.size),
+                // the first press only shows the cursor, in the middle of where play is allowed
+                None => {
+                    let (min, max) = board_state.play_area.unwrap_or(((0, 0), (game_board.size - 1, game_board.size - 1)));
+                    ((min.0 + max.0) / 2, (min.1 + max.1) / 2)
+                }
+            });
+        }
 
-        // Then cast the ray.
-        let hit = rapier_context.cast_ray(
-            ray_pos,
-            ray_dir,
-            20.,
-            true,
-            QueryFilter::only_fixed(),
-        );
+        let (space, submitted) = if let Some(space) = board_state.board_cursor.filter(|_| !board_state.locked) {
+            (Some(space), key_state.play_move || tapped)
+        } else if touch_state.emulating_mouse() {
+            (None, false)
+        } else {
+            let space = pick_space(window.cursor_position().unwrap_or(Vec2::new(0.0, 0.0)));
+            (space, mouse_btn_input.just_released(MouseButton::Left))
+        };
 
 
         let player_turn = board_state.player_turn;
-        let active_transform = if let Some((_entity, toi)) = hit {
-            let space_pos = ray_pos + ray_dir * toi;
-            let space_x = (space_pos.x * (1. / SPACING) + 0.5).floor().min(9.).max(-9.) + 9.;
-            let space_z = (space_pos.z * (1. / SPACING) + 0.5).floor().min(9.).max(-9.) + 9.;
-
-            let space_key = (space_x as usize, space_z as usize);
-            if game_board.spaces.contains_key(&space_key) {
-                Vec3::Y * -9999.
-            } else {
-                if mouse_btn_input.just_released(MouseButton::Left) {
-                    let effects = try_move(&mut game_board, GameBoardMove { player: player_turn, space: space_key});
-                    for effect in effects.iter() {
-                        match effect.effect {
-                            GameBoardEffectType::AddPiece(tried_move) => {
-                                let space_coords = Vec3::new(
-                                    tried_move.space.0 as f32 - 9.,
-                                    0.5,
-                                    tried_move.space.1 as f32 - 9.,
-                                );
-                                let ent_id = commands.spawn(PbrBundle {
-                                    mesh: board_state.stone_mesh.clone(),
-                                    material: if tried_move.player == 1 { board_state.light_stone.clone() } else { board_state.dark_stone.clone() },
-                                    transform: Transform::from_translation(SPACING * space_coords).with_scale(Vec3::new(1.0,0.5,1.0)),
-                                    ..default()
-                                }).id();
-                                board_state.piece_ents.insert(space_key, ent_id);
-                            }
-                            GameBoardEffectType::RemovePiece(removed_move) => {
-                                if let Some(ent_id) = board_state.piece_ents.remove(&removed_move.space) {
-                                    commands.entity(ent_id).despawn_recursive();
-                                }
-                            }
-                            GameBoardEffectType::TogglePlayer => {
-                                audio.play(if player_turn == 0 {
-                                    preloading.sound_01.clone()
-                                } else {
-                                    preloading.sound_02.clone()
-                                });
-                                board_state.player_turn = (player_turn + 1) % 2;
-                            }
-                        }
+        let (active_transform, legal) = if let Some(space_key) = space {
+            let tried_move = GameBoardMove { player: player_turn, space: space_key };
+            match check_move(&game_board, tried_move) {
+                _ if !board_state.in_play_area(space_key) => (Vec3::Y * -9999., true),
+                Err(IllegalMove::Occupied) => (Vec3::Y * -9999., true),
+                // ko and suicide points show a tinted stone that can not be played
+                Err(_) => (space_to_world(space_key, game_board.size), false),
+                Ok(()) => {
+                    if submitted {
+                        actions.send(GameAction::PlaceStone(space_key));
                     }
-
-                    board_state.pause_actions = 0.05;
+                    (space_to_world(space_key, game_board.size), true)
                 }
-                SPACING * Vec3::new(space_x - 9.,0.5,space_z - 9.)
             }
         } else {
-            Vec3::Y * -9999.
+            (Vec3::Y * -9999., true)
         };
 
-        for (mut ap_transform, bap) in active_piece_query.iter_mut() {
+        for (mut ap_transform, mut ap_material, bap) in active_piece_query.iter_mut() {
             if bap.player == player_turn {
                 ap_transform.translation = active_transform;
             } else {
                 ap_transform.translation = Vec3::Y * -9999.;
             }
+            let material = if !legal {
+                &board_state.illegal_stone
+            } else if bap.player == 1 {
+                &board_state.light_stone
+            } else {
+                &board_state.dark_stone
+            };
+            if *ap_material != *material {
+                *ap_material = material.clone();
+            }
         }
 
 
     }
 }
 
+// the rules of the board, plays the stones and passes asked for by any input, bot or peer,
+// the turn changes as the effects are applied so one move is made a frame and later ones wait
+// their turn, actions the rules turn down are sent back as rejections, undo belongs to the game record
+fn apply_game_actions(
+    mut actions: EventReader<GameAction>,
+    mut board_state: ResMut<BoardState>,
+    mut game_board: ResMut<GameBoard>,
+    mut board_effects: EventWriter<GameBoardEffect>,
+    mut rejections: EventWriter<GameActionRejected>,
+    game_mode: Res<GameMode>,
+) {
+    board_state.pending_actions.extend(actions.iter()
+        .filter(|action| matches!(action, GameAction::PlaceStone(_) | GameAction::Pass)));
+    while let Some(action) = board_state.pending_actions.pop_front() {
+        let effects = match action {
+            _ if board_state.locked => Err(ActionRejection::Locked),
+            GameAction::PlaceStone(space) if !board_state.in_play_area(space) => Err(ActionRejection::OutsidePlayArea),
+            GameAction::PlaceStone(space) => {
+                let tried_move = GameBoardMove { player: board_state.player_turn, space };
+                check_move(&game_board, tried_move)
+                    .map(|()| try_move(&mut game_board, tried_move))
+                    .map_err(ActionRejection::Illegal)
+            }
+            // lessons and problems wait for a move
+            GameAction::Pass if *game_mode == GameMode::Free => Ok(pass_move(&mut game_board)),
+            _ => Err(ActionRejection::NotAllowed),
+        };
+        match effects {
+            Ok(effects) => {
+                board_state.pause_actions = 0.05;
+                board_effects.send_batch(effects);
+                break;
+            }
+            Err(reason) => rejections.send(GameActionRejected { action, reason }),
+        }
+    }
+}
+
+// local inputs show illegal points before they are played, a rejection most likely came from elsewhere
+fn report_rejected_actions(
+    mut rejections: EventReader<GameActionRejected>,
+) {
+    for rejected in rejections.iter() {
+        info!("{:?} was not played: {:?}", rejected.action, rejected.reason);
+    }
+}
+
+// moves the keyboard cursor by a step given on screen, up being the board direction furthest from the
+// camera so the keys follow the view, it stays within the play area
+fn step_keyboard_cursor(
+    board_state: &BoardState,
+    space: (usize,usize),
+    step: IVec2,
+    camera_transform: &GlobalTransform,
+    size: usize,
+) -> (usize,usize) {
+    // looking down the forward direction is flat, the camera's up still points up the screen
+    let screen_up = camera_transform.forward() + camera_transform.up();
+    let up = if screen_up.x.abs() > screen_up.z.abs() {
+        IVec2::new(screen_up.x.signum() as i32, 0)
+    } else {
+        IVec2::new(0, screen_up.z.signum() as i32)
+    };
+    let right = IVec2::new(-up.y, up.x);
+    let moved = IVec2::new(space.0 as i32, space.1 as i32) + right * step.x + up * step.y;
+    let (min, max) = board_state.play_area.unwrap_or(((0, 0), (size - 1, size - 1)));
+    (
+        moved.x.clamp(min.0 as i32, max.0 as i32) as usize,
+        moved.y.clamp(min.1 as i32, max.1 as i32) as usize,
+    )
+}
+
+// keeps the stone entities and the player turn in step with the moves made on the game board,
+// stones are animated into and out of place without holding up the board
+#[allow(clippy::too_many_arguments)]
+pub fn apply_board_effects(
+    mut commands: Commands,
+    mut board_state: ResMut<BoardState>,
+    mut board_effects: EventReader<GameBoardEffect>,
+    game_board: Res<GameBoard>,
+    stone_meshes: Res<StoneMeshes>,
+    stone_materials: Res<StoneMaterials>,
+    mut move_numbers: ResMut<MoveNumbers>,
+    preloading: Res<PreLoadingState>,
+    audio: Res<Audio>,
+) {
+    // a stone followed by a player toggle was played as a move and gets the next move number
+    let mut last_added = None;
+    // stones removed right after a move are its captures, taken by the player who moved
+    let mut capturing = None;
+    for effect in board_effects.iter() {
+        match effect.effect {
+            GameBoardEffectType::AddPiece(added_move) => {
+                last_added = Some(added_move.space);
+                capturing = None;
+                let (material, rotation) = stone_materials.random(added_move.player);
+                let drop = StoneDrop::new(space_to_world(added_move.space, game_board.size));
+                let ent_id = commands.spawn(MaterialMeshBundle {
+                    mesh: stone_meshes.mesh(),
+                    material,
+                    transform: Transform::from_translation(drop.start()).with_rotation(rotation),
+                    ..default()
+                }).insert(StoneLod { level: 0 }).insert(drop).id();
+                if let Some(old_ent) = board_state.piece_ents.insert(added_move.space, ent_id) {
+                    commands.entity(old_ent).despawn_recursive();
+                }
+            }
+            GameBoardEffectType::RemovePiece(removed_move) => {
+                move_numbers.numbers.remove(&removed_move.space);
+                if let Some(ent_id) = board_state.piece_ents.remove(&removed_move.space) {
+                    match capturing {
+                        Some(player) => {
+                            let from = space_to_world(removed_move.space, game_board.size);
+                            commands.entity(ent_id)
+                                .remove::<StoneDrop>()
+                                .insert(StoneCapture::new(player, from, lid_drop_point(player, game_board.size)));
+                        }
+                        None => commands.entity(ent_id).despawn_recursive(),
+                    }
+                }
+            }
+            GameBoardEffectType::TogglePlayer => {
+                let player_turn = board_state.player_turn;
+                audio.play(if player_turn == 0 {
+                    preloading.sound_01.clone()
+                } else {
+                    preloading.sound_02.clone()
+                });
+                board_state.player_turn = (player_turn + 1) % 2;
+                // a pass takes a move number too, the last move marker is hidden until the next stone
+                move_numbers.last += 1;
+                if let Some(space) = last_added.take() {
+                    capturing = Some(player_turn);
+                    let last = move_numbers.last;
+                    move_numbers.numbers.insert(space, last);
+                }
+            }
+        }
+    }
+    // numbers for a position replaced with set_position, after its stones have been applied
+    if move_numbers.is_pending() {
+        move_numbers.apply_pending();
+    }
+}
+
+// moves the ring onto the stone with the highest move number
+fn update_last_move_marker(
+    move_numbers: Res<MoveNumbers>,
+    game_board: Res<GameBoard>,
+    stone_meshes: Res<StoneMeshes>,
+    mut marker_query: Query<&mut Transform, With<LastMoveMarker>>,
+) {
+    if !move_numbers.is_changed() && !stone_meshes.is_changed() {
+        return;
+    }
+    let last_space = move_numbers.numbers.iter()
+        .find(|(_, number)| **number == move_numbers.last)
+        .map(|(space, _)| *space);
+    for mut transform in marker_query.iter_mut() {
+        transform.translation = match last_space {
+            // resting on the curved upper face where the ring touches it
+            Some(space) => space_to_world(space, game_board.size)
+                + Vec3::Y * (stone_meshes.shape.surface_height(LAST_MOVE_MARKER_RADIUS) + 0.0001),
+            None => Vec3::Y * -9999.,
+        };
+    }
+}
+
+// puts the ring around the keyboard cursor, on top of a stone if there is one
+fn update_board_cursor_marker(
+    board_state: Res<BoardState>,
+    game_board: Res<GameBoard>,
+    stone_meshes: Res<StoneMeshes>,
+    mut marker_query: Query<&mut Transform, With<BoardCursorMarker>>,
+) {
+    for mut transform in marker_query.iter_mut() {
+        transform.translation = match board_state.board_cursor {
+            Some(space) if game_board.spaces.contains_key(&space) => space_to_world(space, game_board.size)
+                + Vec3::Y * (stone_meshes.shape.surface_height(BOARD_CURSOR_RADIUS) + 0.0001),
+            Some(space) => space_to_world(space, game_board.size) + Vec3::Y * 0.0003,
+            None => Vec3::Y * -9999.,
+        };
+    }
+}
+
 
 // Credit to @doomy on discord.
-fn ray_from_mouse_position(
+// the ray through a point on the window, with the origin at the bottom left like the cursor
+fn ray_from_screen_position(
     window: &Window,
+    screen_position: Vec2,
     camera: &Camera,
     camera_transform: &GlobalTransform,
 ) -> (Vec3, Vec3) {
-    let mouse_position = window.cursor_position().unwrap_or(Vec2::new(0.0, 0.0));
-
-    let x = 2.0 * (mouse_position.x / window.width() as f32) - 1.0;
-    let y = 2.0 * (mouse_position.y / window.height() as f32) - 1.0;
+    let x = 2.0 * (screen_position.x / window.width()) - 1.0;
+    let y = 2.0 * (screen_position.y / window.height()) - 1.0;
 
+    // depth is reversed, 1 is the near plane, and a perspective far plane is at infinity so the
+    // direction comes from a point halfway in depth, for orthographic cameras the rays are parallel
     let camera_inverse_matrix =
         camera_transform.compute_matrix() * camera.projection_matrix().inverse();
-    let near = camera_inverse_matrix * Vec3::new(x, y, -1.0).extend(1.0);
-    let far = camera_inverse_matrix * Vec3::new(x, y, 1.0).extend(1.0);
+    let near = camera_inverse_matrix * Vec3::new(x, y, 1.0).extend(1.0);
+    let further = camera_inverse_matrix * Vec3::new(x, y, 0.5).extend(1.0);
 
     let near = near.truncate() / near.w;
-    let far = far.truncate() / far.w;
-    let dir: Vec3 = far - near;
+    let further = further.truncate() / further.w;
+    let dir: Vec3 = (further - near).normalize_or_zero();
     (near, dir)
 }
@@ -0,0 +1,288 @@
+use crate::board::{GameBoard, GobanState, StoneCapture, StoneLod, StoneMaterials, StoneMeshes, StoneShape, goban_width};
+use crate::game_state::GameState;
+use bevy::{prelude::*, render::mesh::{Indices, PrimitiveTopology}};
+use bevy_rapier3d::prelude::*;
+use rand::Rng;
+
+const BOWL_GAP: f32 = 0.08;
+const BOWL_RADIUS: f32 = 0.055;
+const BOWL_OPENING: f32 = 0.04;
+const BOWL_HEIGHT: f32 = 0.05;
+const LID_RADIUS: f32 = 0.048;
+const LID_FLOOR: f32 = 0.004;
+const LID_WALL_HEIGHT: f32 = 0.012;
+const LID_WALL_THICKNESS: f32 = 0.004;
+const LID_WALL_PIECES: usize = 16;
+const LATHE_SEGMENTS: u32 = 48;
+// captured stones are let go this high over the lid floor, within this share of its radius
+const LID_DROP_HEIGHT: f32 = 0.02;
+const LID_DROP_SPREAD: f32 = 0.5;
+// prisoner counts change a frame or two before the captured stones reach the lid,
+// so the lid contents are only corrected once they have been off for this many frames
+const LID_SYNC_FRAMES: u32 = 3;
+
+// bowls and lids stand beside the board, black on the right and white on the left,
+// every bowl with its lid upturned next to it to hold the stones it has captured
+fn side(player: usize) -> f32 {
+    if player == 0 { 1. } else { -1. }
+}
+
+pub fn bowl_position(player: usize, size: usize) -> Vec3 {
+    let side = side(player);
+    Vec3::new(side * (goban_width(size) / 2. + BOWL_GAP), 0., side * BOWL_RADIUS * 1.2)
+}
+
+pub fn lid_position(player: usize, size: usize) -> Vec3 {
+    let side = side(player);
+    Vec3::new(side * (goban_width(size) / 2. + BOWL_GAP), 0., -side * LID_RADIUS * 1.2)
+}
+
+// a random point above the lid of `player` to let a captured stone fall from
+pub fn lid_drop_point(player: usize, size: usize) -> Vec3 {
+    let mut rng = rand::thread_rng();
+    let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+    let distance = rng.gen_range(0.0..LID_RADIUS * LID_DROP_SPREAD);
+    lid_position(player, size) + Vec3::new(angle.cos() * distance, LID_FLOOR + LID_DROP_HEIGHT, angle.sin() * distance)
+}
+
+// a prisoner lying in a lid, `player` is the one who captured it
+#[derive(Component)]
+pub struct LidStone {
+    pub player: usize,
+}
+
+impl LidStone {
+    // the physics body a stone gets once it is in the lid, the collider is lifted to the stone's middle
+    pub fn body(player: usize, shape: StoneShape) -> (LidStone, RigidBody, Collider) {
+        let half_height = shape.height() / 2.;
+        (
+            LidStone { player },
+            RigidBody::Dynamic,
+            Collider::compound(vec![(Vec3::Y * half_height, Quat::IDENTITY, Collider::cylinder(half_height, shape.radius))]),
+        )
+    }
+}
+
+#[derive(Component)]
+pub struct Bowls;
+
+#[derive(Default, Resource)]
+pub struct BowlsState {
+    // board size the bowls were placed for
+    built_size: usize,
+    bowl_mesh: Handle<Mesh>,
+    fill_mesh: Handle<Mesh>,
+    lid_mesh: Handle<Mesh>,
+    // frames the lid contents have not matched the prisoner counts
+    mismatched_frames: u32,
+}
+
+pub struct BowlsPlugin;
+
+impl Plugin for BowlsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<BowlsState>()
+            .add_startup_system(setup_bowls)
+            .add_system_set(SystemSet::on_update(GameState::Running)
+                .with_system(update_bowls)
+                .with_system(update_lid_contents.after(update_bowls)))
+            ;
+    }
+}
+
+fn setup_bowls(
+    mut bowls: ResMut<BowlsState>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    // the belly of the bowl swells out between the opening and the foot
+    let bowl_profile: Vec<(f32, f32)> = std::iter::once((BOWL_OPENING - 0.003, BOWL_HEIGHT - 0.004))
+        .chain((0..=12).map(|step| {
+            let t = step as f32 / 12.;
+            let radius = BOWL_OPENING + (BOWL_RADIUS * 0.7 - BOWL_OPENING) * t + BOWL_RADIUS * 0.3 * (t * std::f32::consts::PI).sin();
+            (radius, BOWL_HEIGHT * (1. - t) + 0.003 * t)
+        }))
+        .chain([(BOWL_RADIUS * 0.66, 0.), (0., 0.)])
+        .collect();
+    bowls.bowl_mesh = meshes.add(lathe_mesh(&bowl_profile));
+    // the stones still in the bowl, showing in its opening
+    bowls.fill_mesh = meshes.add(lathe_mesh(&[
+        (0., BOWL_HEIGHT - 0.001),
+        (BOWL_OPENING * 0.6, BOWL_HEIGHT - 0.002),
+        (BOWL_OPENING - 0.002, BOWL_HEIGHT - 0.004),
+    ]));
+    // upturned lid, the inside floor, over the rim and down the outside
+    let inner = LID_RADIUS - LID_WALL_THICKNESS;
+    bowls.lid_mesh = meshes.add(lathe_mesh(&[
+        (0., LID_FLOOR),
+        (inner * 0.9, LID_FLOOR),
+        (inner, LID_FLOOR + LID_WALL_HEIGHT * 0.4),
+        (inner, LID_WALL_HEIGHT),
+        (LID_RADIUS - LID_WALL_THICKNESS / 2., LID_WALL_HEIGHT + 0.001),
+        (LID_RADIUS, LID_WALL_HEIGHT),
+        (LID_RADIUS, 0.001),
+        (LID_RADIUS - 0.002, 0.),
+        (0., 0.),
+    ]));
+}
+
+// places the bowls and lids beside the board, again whenever the board size changes
+#[allow(clippy::too_many_arguments)]
+fn update_bowls(
+    mut commands: Commands,
+    game_board: Res<GameBoard>,
+    goban: Res<GobanState>,
+    stone_materials: Res<StoneMaterials>,
+    mut bowls: ResMut<BowlsState>,
+    bowls_query: Query<Entity, With<Bowls>>,
+    lid_stones_query: Query<Entity, With<LidStone>>,
+) {
+    if bowls.built_size == game_board.size {
+        return;
+    }
+    bowls.built_size = game_board.size;
+    // the prisoners are put back into the moved lids by update_lid_contents
+    for ent in bowls_query.iter().chain(lid_stones_query.iter()) {
+        commands.entity(ent).despawn_recursive();
+    }
+
+    for player in 0..2 {
+        commands.spawn(PbrBundle {
+            mesh: bowls.bowl_mesh.clone(),
+            material: goban.wood_material.clone(),
+            transform: Transform::from_translation(bowl_position(player, game_board.size)),
+            ..default()
+        })
+        .insert(Bowls)
+        .insert(RigidBody::Fixed)
+        .insert(Collider::compound(vec![
+            (Vec3::Y * BOWL_HEIGHT / 2., Quat::IDENTITY, Collider::cylinder(BOWL_HEIGHT / 2., BOWL_RADIUS * 0.9)),
+        ]))
+        .with_children(|parent| {
+            parent.spawn(MaterialMeshBundle {
+                mesh: bowls.fill_mesh.clone(),
+                material: stone_materials.for_player(player)[0].clone(),
+                ..default()
+            });
+        });
+
+        // a floor and a ring of wall pieces keep the prisoners in
+        let mut lid_colliders = vec![
+            (Vec3::Y * LID_FLOOR / 2., Quat::IDENTITY, Collider::cylinder(LID_FLOOR / 2., LID_RADIUS)),
+        ];
+        let wall_length = std::f32::consts::TAU * LID_RADIUS / LID_WALL_PIECES as f32;
+        for piece in 0..LID_WALL_PIECES {
+            let angle = std::f32::consts::TAU * piece as f32 / LID_WALL_PIECES as f32;
+            let centre = LID_RADIUS - LID_WALL_THICKNESS / 2.;
+            lid_colliders.push((
+                Vec3::new(angle.cos() * centre, LID_WALL_HEIGHT / 2., angle.sin() * centre),
+                Quat::from_rotation_y(-angle),
+                Collider::cuboid(LID_WALL_THICKNESS / 2., LID_WALL_HEIGHT / 2., wall_length / 2.),
+            ));
+        }
+        commands.spawn(PbrBundle {
+            mesh: bowls.lid_mesh.clone(),
+            material: goban.wood_material.clone(),
+            transform: Transform::from_translation(lid_position(player, game_board.size)),
+            ..default()
+        })
+        .insert(Bowls)
+        .insert(RigidBody::Fixed)
+        .insert(Collider::compound(lid_colliders));
+    }
+}
+
+// keeps as many stones in every lid as its player has prisoners, for counts that change without a capture
+// such as stepping through a game record, stones that are flying to a lid already count for it
+#[allow(clippy::too_many_arguments)]
+fn update_lid_contents(
+    mut commands: Commands,
+    game_board: Res<GameBoard>,
+    stone_meshes: Res<StoneMeshes>,
+    stone_materials: Res<StoneMaterials>,
+    mut bowls: ResMut<BowlsState>,
+    lid_stones_query: Query<(Entity, &LidStone)>,
+    captures_query: Query<&StoneCapture>,
+) {
+    let mut counts = [0, 0];
+    for (_, lid_stone) in lid_stones_query.iter() {
+        counts[lid_stone.player] += 1;
+    }
+    for capture in captures_query.iter() {
+        counts[capture.player] += 1;
+    }
+    if counts == game_board.prisoners {
+        if bowls.mismatched_frames > 0 {
+            bowls.mismatched_frames = 0;
+        }
+        return;
+    }
+    bowls.mismatched_frames += 1;
+    if bowls.mismatched_frames < LID_SYNC_FRAMES {
+        return;
+    }
+    bowls.mismatched_frames = 0;
+
+    for (player, (count, prisoners)) in counts.into_iter().zip(game_board.prisoners).enumerate() {
+        if count > prisoners {
+            let extra = lid_stones_query.iter().filter(|(_, lid_stone)| lid_stone.player == player);
+            for (ent, _) in extra.take(count - prisoners) {
+                commands.entity(ent).despawn_recursive();
+            }
+        }
+        // stacked up so they do not start inside each other
+        for index in count..prisoners {
+            let (material, rotation) = stone_materials.random(1 - player);
+            let position = lid_drop_point(player, game_board.size)
+                + Vec3::Y * (index - count) as f32 * stone_meshes.shape.height() * 1.5;
+            commands.spawn(MaterialMeshBundle {
+                mesh: stone_meshes.mesh(),
+                material,
+                transform: Transform::from_translation(position).with_rotation(rotation),
+                ..default()
+            })
+            .insert(StoneLod { level: 0 })
+            .insert(LidStone::body(player, stone_meshes.shape));
+        }
+    }
+}
+
+// a surface of revolution around the y axis from (radius, height) points, listed so that
+// the outside of the surface is on the left going from one point to the next
+fn lathe_mesh(profile: &[(f32, f32)]) -> Mesh {
+    let mut positions: Vec<[f32; 3]> = vec![];
+    let mut normals: Vec<[f32; 3]> = vec![];
+    let mut uvs: Vec<[f32; 2]> = vec![];
+    let mut indices: Vec<u32> = vec![];
+
+    let last = profile.len() - 1;
+    for (index, (radius, height)) in profile.iter().enumerate() {
+        // the profile normal averages the directions of the neighbouring edges
+        let (before_r, before_h) = profile[index.saturating_sub(1)];
+        let (after_r, after_h) = profile[(index + 1).min(last)];
+        let normal = Vec2::new(-(after_h - before_h), after_r - before_r).normalize_or_zero();
+        for segment in 0..=LATHE_SEGMENTS {
+            let azimuth = std::f32::consts::TAU * segment as f32 / LATHE_SEGMENTS as f32;
+            let (sin, cos) = azimuth.sin_cos();
+            positions.push([radius * cos, *height, radius * sin]);
+            normals.push([normal.x * cos, normal.y, normal.x * sin]);
+            uvs.push([segment as f32 / LATHE_SEGMENTS as f32, index as f32 / last as f32]);
+        }
+    }
+
+    let row = LATHE_SEGMENTS + 1;
+    for index in 0..last as u32 {
+        for segment in 0..LATHE_SEGMENTS {
+            let a = index * row + segment;
+            let b = a + row;
+            indices.extend([a, a + 1, b + 1, a, b + 1, b]);
+        }
+    }
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh
+}
@@ -0,0 +1,115 @@
+use crate::actions::KeyInputState;
+use crate::board::{GameBoard, grid_to_world, space_name, spawn_world_label};
+use crate::game_state::GameState;
+use crate::loading::PreLoadingState;
+use bevy::prelude::*;
+
+// distance of the labels from the outer lines, in line spacings
+const COORDINATE_LABEL_OFFSET: f32 = 0.9;
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Default)]
+pub enum CoordinateStyle {
+    // letters A-T without I from the left, numbers from the bottom
+    #[default]
+    Western,
+    // numbers from the right and from the top, the bundled font has no kanji for the rows
+    Japanese,
+    // the lowercase letters used for points in sgf files, from the top left
+    Sgf,
+    Hidden,
+}
+
+impl CoordinateStyle {
+    fn next(self) -> Self {
+        match self {
+            CoordinateStyle::Western => CoordinateStyle::Japanese,
+            CoordinateStyle::Japanese => CoordinateStyle::Sgf,
+            CoordinateStyle::Sgf => CoordinateStyle::Hidden,
+            CoordinateStyle::Hidden => CoordinateStyle::Western,
+        }
+    }
+
+    // labels for column `x` and row `y` of a board of `size`
+    pub fn labels(self, x: usize, y: usize, size: usize) -> (String, String) {
+        match self {
+            CoordinateStyle::Western => {
+                let name = space_name((x, y), size);
+                (name[..1].to_string(), name[1..].to_string())
+            }
+            CoordinateStyle::Japanese => ((size - x).to_string(), (y + 1).to_string()),
+            CoordinateStyle::Sgf => (((b'a' + x as u8) as char).to_string(), ((b'a' + y as u8) as char).to_string()),
+            CoordinateStyle::Hidden => (String::new(), String::new()),
+        }
+    }
+}
+
+#[derive(Default, Resource)]
+pub struct CoordinateLabels {
+    pub style: CoordinateStyle,
+    // board size the current labels were built for
+    built_size: usize,
+}
+
+#[derive(Component)]
+pub struct CoordinateLabel;
+
+pub struct CoordinateLabelsPlugin;
+
+impl Plugin for CoordinateLabelsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<CoordinateLabels>()
+            .add_system_set(SystemSet::on_update(GameState::Running)
+                .with_system(update_coordinate_labels))
+            ;
+    }
+}
+
+// rebuilds the labels on all four sides when the style or the board size changes
+fn update_coordinate_labels(
+    mut commands: Commands,
+    key_state: Res<KeyInputState>,
+    game_board: Res<GameBoard>,
+    pre_loading_state: Res<PreLoadingState>,
+    mut coordinates: ResMut<CoordinateLabels>,
+    labels_query: Query<Entity, With<CoordinateLabel>>,
+) {
+    if key_state.cycle_coordinates {
+        coordinates.style = coordinates.style.next();
+    } else if coordinates.built_size == game_board.size && !coordinates.is_changed() {
+        return;
+    }
+    coordinates.built_size = game_board.size;
+    for ent in labels_query.iter() {
+        commands.entity(ent).despawn_recursive();
+    }
+    if coordinates.style == CoordinateStyle::Hidden {
+        return;
+    }
+
+    let size = game_board.size;
+    let low = -COORDINATE_LABEL_OFFSET;
+    let high = (size - 1) as f32 + COORDINATE_LABEL_OFFSET;
+    for i in 0..size {
+        let (column, row) = coordinates.style.labels(i, i, size);
+        let line = i as f32;
+        for (anchor, value) in [
+            (grid_to_world(line, low, size), &column),
+            (grid_to_world(line, high, size), &column),
+            (grid_to_world(low, line, size), &row),
+            (grid_to_world(high, line, size), &row),
+        ] {
+            let label_ent = spawn_world_label(
+                &mut commands,
+                anchor,
+                value.clone(),
+                TextStyle {
+                    font: pre_loading_state.font_handle.clone(),
+                    font_size: 14.0,
+                    color: Color::rgb(0.12, 0.08, 0.04),
+                },
+            );
+            commands.entity(label_ent).insert(CoordinateLabel);
+        }
+    }
+}
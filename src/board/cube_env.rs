@@ -1,8 +1,9 @@
-use std::f32::consts::PI;
-
+use crate::actions::KeyInputState;
+use crate::board::ThemeState;
+use crate::game_state::GameState;
+use crate::persistence::{load_data_file, save_data_file};
 use bevy::{
     asset::LoadState,
-    input::mouse::MouseMotion,
     pbr::{MaterialPipeline, MaterialPipelineKey},
     prelude::*,
     reflect::TypeUuid,
@@ -11,116 +12,254 @@ use bevy::{
         render_asset::RenderAssets,
         render_resource::{
             AsBindGroup, AsBindGroupError, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
-            BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType,
+            BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType, Extent3d,
             OwnedBindingResource, PreparedBindGroup, RenderPipelineDescriptor, SamplerBindingType,
-            ShaderRef, ShaderStages, SpecializedMeshPipelineError, TextureSampleType,
-            TextureViewDescriptor, TextureViewDimension,
+            ShaderRef, ShaderStages, SpecializedMeshPipelineError, TextureDimension, TextureFormat,
+            TextureSampleType, TextureViewDescriptor, TextureViewDimension,
         },
         renderer::RenderDevice,
         texture::{CompressedImageFormats, FallbackImage},
     },
 };
 
+// remembers the chosen environment between runs
+const ENVIRONMENT_CHOICE_FILE: &str = "environment.txt";
+const GRADIENT_CUBEMAP_SIZE: u32 = 32;
+
+// a sky around the board that also lights the stones, either a cubemap texture or a generated gradient
+pub struct Environment {
+    pub name: &'static str,
+    // etc2 compressed cubemap, the gradient is shown instead where the gpu cannot sample etc2
+    pub cubemap: Option<&'static str>,
+    // zenith, horizon and ground colours, no sky at all when both are missing
+    pub gradient: Option<[Color; 3]>,
+    // how strongly the sky lights the stones, diffuse and in their reflections
+    pub lighting: f32,
+}
+
+pub const ENVIRONMENTS: [Environment; 3] = [
+    Environment {
+        name: "Ryfjallet",
+        cubemap: Some("textures/Ryfjallet_cubemap_etc2.ktx2"),
+        gradient: Some([Color::rgb(0.36, 0.52, 0.74), Color::rgb(0.78, 0.82, 0.86), Color::rgb(0.22, 0.22, 0.2)]),
+        lighting: 0.35,
+    },
+    Environment {
+        name: "Studio",
+        cubemap: None,
+        gradient: Some([Color::rgb(0.55, 0.55, 0.57), Color::rgb(0.32, 0.32, 0.33), Color::rgb(0.12, 0.12, 0.12)]),
+        lighting: 0.6,
+    },
+    Environment {
+        name: "None",
+        cubemap: None,
+        gradient: None,
+        lighting: 0.,
+    },
+];
+
+#[derive(Default, Resource)]
+pub struct CubeEnvState {
+    pub current: usize,
+    // whether the gpu can sample the etc2 cubemaps
+    pub etc2_supported: bool,
+    // the cubemap image of the current environment, still loading until `is_loaded`
+    image_handle: Option<Handle<Image>>,
+    is_loaded: bool,
+    sky_mesh: Handle<Mesh>,
+}
+
+impl CubeEnvState {
+    pub fn environment(&self) -> &'static Environment {
+        &ENVIRONMENTS[self.current]
+    }
+
+    // the sky to light the stones with, only once it can be sampled as a cube
+    pub fn lighting_image(&self) -> Option<Handle<Image>> {
+        if self.is_loaded { self.image_handle.clone() } else { None }
+    }
+}
+
+#[derive(Component)]
+pub struct Skybox;
+
+pub struct CubeEnvPlugin;
 
 impl Plugin for CubeEnvPlugin {
     fn build(&self, app: &mut App) {
         app
             .add_plugin(MaterialPlugin::<CubemapMaterial>::default())
-            .add_startup_system(setup)
+            .init_resource::<CubeEnvState>()
+            .add_startup_system(setup_cube_env)
             .add_system(asset_loaded)
+            .add_system_set(SystemSet::on_update(GameState::Running)
+                .with_system(update_environment_choice.before(asset_loaded)))
             ;
     }
 }
 
+fn setup_cube_env(
+    mut cube_env: ResMut<CubeEnvState>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut images: ResMut<Assets<Image>>,
+    asset_server: Res<AssetServer>,
+    render_device: Res<RenderDevice>,
+) {
+    cube_env.etc2_supported = CompressedImageFormats::from_features(render_device.features())
+        .contains(CompressedImageFormats::ETC2);
+    if !cube_env.etc2_supported {
+        info!("No ETC2 support, cubemap environments fall back to gradients");
+    }
+    cube_env.sky_mesh = meshes.add(Mesh::from(shape::Cube { size: 10000.0 }));
 
-// (
-//     ,
-//     CompressedImageFormats::ETC2,
-// ),
+    let saved = load_data_file(ENVIRONMENT_CHOICE_FILE).unwrap_or_default();
+    let current = ENVIRONMENTS.iter().position(|environment| environment.name == saved.trim()).unwrap_or(0);
+    select_environment(&mut cube_env, current, &asset_server, &mut images);
+}
 
-#[derive(Resource)]
-struct Cubemap {
-    is_loaded: bool,
+// starts loading the sky of environment `index`, the skybox picks it up in asset_loaded
+fn select_environment(
+    cube_env: &mut CubeEnvState,
     index: usize,
-    image_handle: Handle<Image>,
+    asset_server: &AssetServer,
+    images: &mut Assets<Image>,
+) {
+    cube_env.current = index;
+    cube_env.is_loaded = false;
+    let environment = cube_env.environment();
+    cube_env.image_handle = match (environment.cubemap, environment.gradient) {
+        (Some(path), _) if cube_env.etc2_supported => Some(asset_server.load(path)),
+        (_, Some(colors)) => Some(images.add(gradient_cubemap(colors))),
+        _ => None,
+    };
 }
 
-fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
-    // directional 'sun' light
-    commands.spawn(DirectionalLightBundle {
-        directional_light: DirectionalLight {
-            illuminance: 32000.0,
-            ..default()
-        },
-        transform: Transform::from_xyz(0.0, 2.0, 0.0)
-            .with_rotation(Quat::from_rotation_x(-PI / 4.)),
-        ..default()
-    });
-
-    let skybox_handle = asset_server.load("textures/Ryfjallet_cubemap_etc2.ktx2");
-
-
-    // ambient light
-    // NOTE: The ambient light is used to scale how bright the environment map is so with a bright
-    // environment map, use an appropriate colour and brightness to match
-    commands.insert_resource(AmbientLight {
-        color: Color::rgb_u8(210, 220, 240),
-        brightness: 1.0,
-    });
-
-    commands.insert_resource(Cubemap {
-        is_loaded: false,
-        index: 0,
-        image_handle: skybox_handle,
-    });
+fn update_environment_choice(
+    key_state: Res<KeyInputState>,
+    mut cube_env: ResMut<CubeEnvState>,
+    mut theme_state: ResMut<ThemeState>,
+    asset_server: Res<AssetServer>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    if !key_state.cycle_environment {
+        return;
+    }
+    let next = (cube_env.current + 1) % ENVIRONMENTS.len();
+    select_environment(&mut cube_env, next, &asset_server, &mut images);
+    // the stones stop sampling the old sky until the new one is ready
+    theme_state.reapply();
+    if let Err(err) = save_data_file(ENVIRONMENT_CHOICE_FILE, cube_env.environment().name) {
+        warn!("Could not save environment choice: {}", err);
+    }
 }
 
+// shows the sky once its image is ready, or hides the skybox for environments without one
+#[allow(clippy::too_many_arguments)]
 fn asset_loaded(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut images: ResMut<Assets<Image>>,
-    mut meshes: ResMut<Assets<Mesh>>,
     mut cubemap_materials: ResMut<Assets<CubemapMaterial>>,
-    mut cubemap: ResMut<Cubemap>,
-    cubes: Query<&Handle<CubemapMaterial>>,
+    mut cube_env: ResMut<CubeEnvState>,
+    mut theme_state: ResMut<ThemeState>,
+    mut skyboxes: Query<(&Handle<CubemapMaterial>, &mut Visibility), With<Skybox>>,
 ) {
-    if !cubemap.is_loaded
-        && asset_server.get_load_state(cubemap.image_handle.clone_weak()) == LoadState::Loaded
-    {
-        info!("Swapping to {}...", cubemap.index);
-        let mut image = images.get_mut(&cubemap.image_handle).unwrap();
-        // NOTE: PNGs do not have any metadata that could indicate they contain a cubemap texture,
-        // so they appear as one texture. The following code reconfigures the texture as necessary.
-        if image.texture_descriptor.array_layer_count() == 1 {
-            image.reinterpret_stacked_2d_as_array(
-                image.texture_descriptor.size.height / image.texture_descriptor.size.width,
-            );
-            image.texture_view_descriptor = Some(TextureViewDescriptor {
-                dimension: Some(TextureViewDimension::Cube),
-                ..default()
-            });
+    if cube_env.is_loaded {
+        return;
+    }
+    let Some(image_handle) = cube_env.image_handle.clone() else {
+        cube_env.is_loaded = true;
+        for (_, mut visibility) in skyboxes.iter_mut() {
+            visibility.is_visible = false;
         }
-
-        // spawn cube
-        let mut updated = false;
-        for handle in cubes.iter() {
-            if let Some(material) = cubemap_materials.get_mut(handle) {
-                updated = true;
-                material.base_color_texture = Some(cubemap.image_handle.clone_weak());
-            }
+        return;
+    };
+    match asset_server.get_load_state(image_handle.clone_weak()) {
+        // generated gradients are added directly and have no load state
+        LoadState::Loaded | LoadState::NotLoaded => {}
+        LoadState::Failed => {
+            warn!("Could not load the {} cubemap, using its gradient", cube_env.environment().name);
+            cube_env.image_handle = cube_env.environment().gradient.map(|colors| images.add(gradient_cubemap(colors)));
+            return;
         }
-        if !updated {
-            commands.spawn(MaterialMeshBundle::<CubemapMaterial> {
-                mesh: meshes.add(Mesh::from(shape::Cube { size: 10000.0 })),
-                material: cubemap_materials.add(CubemapMaterial {
-                    base_color_texture: Some(cubemap.image_handle.clone_weak()),
-                }),
-                ..default()
-            });
+        _ => return,
+    }
+    let Some(image) = images.get_mut(&image_handle) else {
+        return;
+    };
+    info!("Swapping to {}...", cube_env.environment().name);
+    // NOTE: PNGs do not have any metadata that could indicate they contain a cubemap texture,
+    // so they appear as one texture. The following code reconfigures the texture as necessary.
+    if image.texture_descriptor.array_layer_count() == 1 {
+        image.reinterpret_stacked_2d_as_array(
+            image.texture_descriptor.size.height / image.texture_descriptor.size.width,
+        );
+        image.texture_view_descriptor = Some(TextureViewDescriptor {
+            dimension: Some(TextureViewDimension::Cube),
+            ..default()
+        });
+    }
+
+    // spawn cube
+    let mut updated = false;
+    for (handle, mut visibility) in skyboxes.iter_mut() {
+        if let Some(material) = cubemap_materials.get_mut(handle) {
+            updated = true;
+            material.base_color_texture = Some(image_handle.clone());
+            visibility.is_visible = true;
         }
+    }
+    if !updated {
+        commands.spawn(MaterialMeshBundle::<CubemapMaterial> {
+            mesh: cube_env.sky_mesh.clone(),
+            material: cubemap_materials.add(CubemapMaterial {
+                base_color_texture: Some(image_handle),
+            }),
+            ..default()
+        }).insert(Skybox);
+    }
+
+    cube_env.is_loaded = true;
+    theme_state.reapply();
+}
 
-        cubemap.is_loaded = true;
+// a cubemap shading from `colors` zenith through horizon to ground, only the height of a direction matters
+fn gradient_cubemap(colors: [Color; 3]) -> Image {
+    let [zenith, horizon, ground] = colors;
+    let size = GRADIENT_CUBEMAP_SIZE;
+    let mut data = Vec::with_capacity((size * size * 6 * 4) as usize);
+    // faces in +x, -x, +y, -y, +z, -z order
+    for face in 0..6 {
+        for y in 0..size {
+            for x in 0..size {
+                let s = 2. * (x as f32 + 0.5) / size as f32 - 1.;
+                let t = 2. * (y as f32 + 0.5) / size as f32 - 1.;
+                let direction = match face {
+                    0 => Vec3::new(1., -t, -s),
+                    1 => Vec3::new(-1., -t, s),
+                    2 => Vec3::new(s, 1., t),
+                    3 => Vec3::new(s, -1., -t),
+                    4 => Vec3::new(s, -t, 1.),
+                    _ => Vec3::new(-s, -t, -1.),
+                };
+                let height = direction.normalize().y;
+                let (far, amount) = if height >= 0. { (zenith, height.sqrt()) } else { (ground, (-height).sqrt()) };
+                let color = Vec4::from(horizon).lerp(Vec4::from(far), amount);
+                data.extend(color.to_array().map(|c| (c.clamp(0., 1.) * 255.) as u8));
+            }
+        }
     }
+    let mut image = Image::new(
+        Extent3d { width: size, height: size, depth_or_array_layers: 6 },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    );
+    image.texture_view_descriptor = Some(TextureViewDescriptor {
+        dimension: Some(TextureViewDimension::Cube),
+        ..default()
+    });
+    image
 }
 
 #[derive(Debug, Clone, TypeUuid)]
@@ -154,7 +293,7 @@ impl AsBindGroup for CubemapMaterial {
         render_device: &RenderDevice,
         images: &RenderAssets<Image>,
         _fallback_image: &FallbackImage,
-    ) -> Result<PreparedBindGroup<Self::Data>, AsBindGroupError> {
+    ) -> Result<PreparedBindGroup<Self>, AsBindGroupError> {
         let base_color_texture = self
             .base_color_texture
             .as_ref()
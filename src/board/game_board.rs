@@ -1,16 +1,41 @@
 use bevy::prelude::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-#[derive(Default, Resource)]
+#[derive(Clone, Resource)]
 pub struct GameBoard {
+    pub size: usize,
     pub spaces: HashMap<(usize,usize),usize>,
+    // the point that may not be played next because it would retake a ko
+    pub ko: Option<(usize,usize)>,
+    // stones captured by each player
+    pub prisoners: [usize; 2],
 }
 
+impl Default for GameBoard {
+    fn default() -> Self {
+        Self {
+            size: 19,
+            spaces: HashMap::new(),
+            ko: None,
+            prisoners: [0, 0],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum IllegalMove {
+    Occupied,
+    Ko,
+    Suicide,
+}
+
+#[derive(Clone, Copy, Debug)]
 pub enum GameBoardEffectType {
     TogglePlayer,
     AddPiece(GameBoardMove),
     RemovePiece(GameBoardMove),
 }
+#[derive(Clone, Copy, Debug)]
 pub struct GameBoardEffect {
     pub effect: GameBoardEffectType,
 }
@@ -21,85 +46,383 @@ pub struct GameBoardMove {
     pub space: (usize, usize),
 }
 
-pub fn try_move(game_board: &mut GameBoard, tried_move: GameBoardMove) -> Vec<GameBoardEffect> {
-    if game_board.spaces.contains_key(&tried_move.space) {
-        vec![]
-    } else {
-        // add piece to board
-        game_board.spaces.insert(tried_move.space, tried_move.player);
-
-        let mut effects = vec![
-            GameBoardEffect { effect: GameBoardEffectType::AddPiece(tried_move) },
-            GameBoardEffect { effect: GameBoardEffectType::TogglePlayer },
-        ];
-
-        // check for surrounded groups to remove
-        let surrounded = get_surrounded_positions(game_board, tried_move);
-        for pos in surrounded {
-            let piece_player = game_board.spaces.remove(&pos).unwrap();
-            effects.push(GameBoardEffect { effect: GameBoardEffectType::RemovePiece(
-                GameBoardMove { player: piece_player, space: pos }) })
-        }
+// a connected chain of stones of one player and its empty neighbouring spaces
+#[derive(Clone, Debug)]
+pub struct GameBoardGroup {
+    pub player: usize,
+    pub spaces: Vec<(usize,usize)>,
+    pub liberties: Vec<(usize,usize)>,
+}
 
-        effects
+impl GameBoardGroup {
+    pub fn is_atari(&self) -> bool {
+        self.liberties.len() == 1
     }
 }
 
-pub fn get_surrounded_positions(game_board: &GameBoard, last_move: GameBoardMove) -> Vec<(usize,usize)> {
-    let mut group_stats: Vec<(usize,bool)> = vec![];
-    let mut space_groups = HashMap::<(usize,usize),usize>::new();
-    let mut last_move_group: Option<usize> = None;
-
-    for i in 0..19 {
-        for j in 0..19 {
-            if game_board.spaces.contains_key(&(i, j)) {
-                let space_player = *game_board.spaces.get(&(i, j)).unwrap();
-                let i_group_id = if i > 0 && space_groups.contains_key(&(i - 1, j)) && (*game_board.spaces.get(&(i - 1, j)).unwrap() == space_player) {
-                    Some(*space_groups.get(&(i - 1, j)).unwrap())
-                } else {
-                    None
-                };
-                let group_id = if j > 0 && game_board.spaces.contains_key(&(i, j - 1)) && (*game_board.spaces.get(&(i, j - 1)).unwrap() == space_player) {
-                    let j_group_id = *space_groups.get(&(i, j - 1)).unwrap();
-                    if i_group_id.is_some() && Some(j_group_id) != i_group_id {
-                        for (_, group_id) in space_groups.iter_mut() {
-                            if *group_id == j_group_id {
-                                *group_id = i_group_id.unwrap();
-                            }
+impl GameBoard {
+    // orthogonally adjacent spaces that are on the board
+    pub fn neighbors(&self, space: (usize,usize)) -> Vec<(usize,usize)> {
+        let mut neighbors = Vec::with_capacity(4);
+        if space.0 > 0 { neighbors.push((space.0 - 1, space.1)); }
+        if space.1 > 0 { neighbors.push((space.0, space.1 - 1)); }
+        if space.0 + 1 < self.size { neighbors.push((space.0 + 1, space.1)); }
+        if space.1 + 1 < self.size { neighbors.push((space.0, space.1 + 1)); }
+        neighbors
+    }
+
+    // the group containing the stone at `space`, if any
+    pub fn group_at(&self, space: (usize,usize)) -> Option<GameBoardGroup> {
+        let player = *self.spaces.get(&space)?;
+        let mut spaces = vec![space];
+        let mut visited = HashSet::from([space]);
+        let mut liberties = HashSet::new();
+        let mut i = 0;
+        while i < spaces.len() {
+            for neighbor in self.neighbors(spaces[i]) {
+                match self.spaces.get(&neighbor) {
+                    None => { liberties.insert(neighbor); }
+                    Some(&p) if p == player && visited.insert(neighbor) => spaces.push(neighbor),
+                    _ => {}
+                }
+            }
+            i += 1;
+        }
+        let mut liberties: Vec<(usize,usize)> = liberties.into_iter().collect();
+        liberties.sort();
+        Some(GameBoardGroup { player, spaces, liberties })
+    }
+
+    // area score per player: stones plus empty regions that border only that player's stones
+    pub fn area_score(&self) -> [usize; 2] {
+        let mut score = [0, 0];
+        for player in self.spaces.values() {
+            score[*player] += 1;
+        }
+        let mut seen = HashSet::new();
+        for i in 0..self.size {
+            for j in 0..self.size {
+                if self.spaces.contains_key(&(i, j)) || !seen.insert((i, j)) {
+                    continue;
+                }
+                let mut region = vec![(i, j)];
+                let mut borders = [false, false];
+                let mut k = 0;
+                while k < region.len() {
+                    for neighbor in self.neighbors(region[k]) {
+                        match self.spaces.get(&neighbor) {
+                            Some(player) => borders[*player] = true,
+                            None => if seen.insert(neighbor) { region.push(neighbor) },
                         }
-                        i_group_id.unwrap()
-                    } else {
-                        j_group_id
                     }
-                } else {
-                    if i_group_id.is_some() {
-                        i_group_id.unwrap()
-                    } else {
-                        group_stats.push((space_player, false));
-                        group_stats.len() - 1
-                    }
-                };
-                group_stats[group_id].1 = group_stats[group_id].1 ||
-                    (i > 0 && !game_board.spaces.contains_key(&(i - 1, j))) ||
-                    (j > 0 && !game_board.spaces.contains_key(&(i, j - 1))) ||
-                    (i < 18 && !game_board.spaces.contains_key(&(i + 1, j))) ||
-                    (j < 18 && !game_board.spaces.contains_key(&(i, j + 1)));
-                space_groups.insert((i, j), group_id);
-                if i == last_move.space.0 && j == last_move.space.1 {
-                    last_move_group = Some(group_id);
+                    k += 1;
+                }
+                match borders {
+                    [true, false] => score[0] += region.len(),
+                    [false, true] => score[1] += region.len(),
+                    _ => {}
                 }
             }
         }
+        score
+    }
+
+    // every group on the board, each stone appearing in exactly one group
+    pub fn groups(&self) -> Vec<GameBoardGroup> {
+        let mut keys: Vec<&(usize,usize)> = self.spaces.keys().collect();
+        keys.sort();
+        let mut seen = HashSet::new();
+        let mut groups = vec![];
+        for space in keys {
+            if seen.contains(space) {
+                continue;
+            }
+            let group = self.group_at(*space).unwrap();
+            seen.extend(group.spaces.iter().copied());
+            groups.push(group);
+        }
+        groups
+    }
+}
+
+// western board coordinate such as "D16", columns skip the letter I
+pub fn space_name(space: (usize,usize), size: usize) -> String {
+    let column = b"ABCDEFGHJKLMNOPQRSTUVWXYZ".get(space.0).map_or('?', |c| *c as char);
+    format!("{}{}", column, size - space.1)
+}
+
+// why `tried_move` would be rejected by try_move, if it would
+pub fn check_move(game_board: &GameBoard, tried_move: GameBoardMove) -> Result<(), IllegalMove> {
+    if game_board.spaces.contains_key(&tried_move.space) {
+        return Err(IllegalMove::Occupied);
+    }
+    if game_board.ko == Some(tried_move.space) {
+        return Err(IllegalMove::Ko);
+    }
+
+    // a move is fine if it has an empty neighbour, joins a group with another liberty,
+    // or takes the last liberty of an opponent group
+    let legal = game_board.neighbors(tried_move.space).into_iter().any(|neighbor| {
+        match game_board.group_at(neighbor) {
+            None => true,
+            Some(group) if group.player == tried_move.player => group.liberties.len() > 1,
+            Some(group) => group.is_atari(),
+        }
+    });
+    if legal { Ok(()) } else { Err(IllegalMove::Suicide) }
+}
+
+pub fn try_move(game_board: &mut GameBoard, tried_move: GameBoardMove) -> Vec<GameBoardEffect> {
+    if check_move(game_board, tried_move).is_err() {
+        return vec![];
+    }
+
+    // add piece to board
+    game_board.spaces.insert(tried_move.space, tried_move.player);
+
+    let mut effects = vec![
+        GameBoardEffect { effect: GameBoardEffectType::AddPiece(tried_move) },
+        GameBoardEffect { effect: GameBoardEffectType::TogglePlayer },
+    ];
+
+    // check for surrounded groups to remove
+    let surrounded = get_surrounded_positions(game_board, tried_move);
+    for pos in surrounded {
+        let piece_player = game_board.spaces.remove(&pos).unwrap();
+        game_board.prisoners[tried_move.player] += 1;
+        effects.push(GameBoardEffect { effect: GameBoardEffectType::RemovePiece(
+            GameBoardMove { player: piece_player, space: pos }) })
+    }
+
+    // a single stone taking a single stone can not be retaken straight away
+    game_board.ko = None;
+    if let [_, _, GameBoardEffect { effect: GameBoardEffectType::RemovePiece(captured) }] = effects[..] {
+        let group = game_board.group_at(tried_move.space).unwrap();
+        if group.spaces.len() == 1 && group.liberties == [captured.space] {
+            game_board.ko = Some(captured.space);
+        }
+    }
+
+    effects
+}
+
+// the player to move passes, which also lifts a ko ban
+pub fn pass_move(game_board: &mut GameBoard) -> Vec<GameBoardEffect> {
+    game_board.ko = None;
+    vec![GameBoardEffect { effect: GameBoardEffectType::TogglePlayer }]
+}
+
+// replaces the whole position, returning the effects that turn the old position into the new one
+pub fn set_position(game_board: &mut GameBoard, spaces: HashMap<(usize,usize),usize>) -> Vec<GameBoardEffect> {
+    let old_spaces = std::mem::replace(&mut game_board.spaces, spaces);
+    game_board.ko = None;
+    let mut effects = vec![];
+
+    let mut removed: Vec<((usize,usize),usize)> = old_spaces.iter()
+        .filter(|(space, player)| game_board.spaces.get(space) != Some(player))
+        .map(|(space, player)| (*space, *player))
+        .collect();
+    removed.sort();
+    for (space, player) in removed {
+        effects.push(GameBoardEffect { effect: GameBoardEffectType::RemovePiece(
+            GameBoardMove { player, space }) });
     }
 
+    let mut added: Vec<((usize,usize),usize)> = game_board.spaces.iter()
+        .filter(|(space, player)| old_spaces.get(space) != Some(player))
+        .map(|(space, player)| (*space, *player))
+        .collect();
+    added.sort();
+    for (space, player) in added {
+        effects.push(GameBoardEffect { effect: GameBoardEffectType::AddPiece(
+            GameBoardMove { player, space }) });
+    }
+
+    effects
+}
+
+pub fn get_surrounded_positions(game_board: &GameBoard, last_move: GameBoardMove) -> Vec<(usize,usize)> {
     let mut surrounded_pos: Vec<(usize,usize)> = vec![];
 
-    // todo handle self-kill case
-    for (space_key, group_id) in space_groups {
-        if (!group_stats[group_id].1) && (last_move_group != Some(group_id)) {
-            surrounded_pos.push(space_key);
+    for group in game_board.groups() {
+        if group.liberties.is_empty() && !group.spaces.contains(&last_move.space) {
+            surrounded_pos.extend(group.spaces);
         }
     }
 
     surrounded_pos
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    // builds a board from rows of `X` (black), `O` (white) and `.`, one row per second coordinate
+    pub(crate) fn board_from_diagram(rows: &[&str]) -> GameBoard {
+        let mut board = GameBoard { size: rows.len(), ..Default::default() };
+        for (j, row) in rows.iter().enumerate() {
+            for (i, c) in row.chars().filter(|c| !c.is_whitespace()).enumerate() {
+                match c {
+                    'X' => { board.spaces.insert((i, j), 0); }
+                    'O' => { board.spaces.insert((i, j), 1); }
+                    _ => {}
+                }
+            }
+        }
+        board
+    }
+
+    // a black wall on the left with three eyes, a white wall splitting the right side in two
+    const WALLS: [&str; 5] = [
+        ". X O . .",
+        "X X O . .",
+        ". X O O O",
+        "X X O . .",
+        ". X O . .",
+    ];
+
+    #[test]
+    fn group_at_follows_the_chain_and_counts_shared_liberties_once() {
+        let board = board_from_diagram(&WALLS);
+        let black = board.group_at((0, 1)).unwrap();
+        assert_eq!(black.player, 0);
+        assert_eq!(black.spaces.len(), 7);
+        assert_eq!(black.liberties, vec![(0, 0), (0, 2), (0, 4)]);
+        let white = board.group_at((4, 2)).unwrap();
+        assert_eq!(white.player, 1);
+        assert_eq!(white.spaces.len(), 7);
+        assert_eq!(white.liberties, vec![(3, 0), (3, 1), (3, 3), (3, 4), (4, 1), (4, 3)]);
+        assert!(board.group_at((0, 0)).is_none());
+    }
+
+    #[test]
+    fn group_at_sees_atari() {
+        let board = board_from_diagram(&[
+            ". . .",
+            "X O X",
+            ". X .",
+        ]);
+        let white = board.group_at((1, 1)).unwrap();
+        assert!(white.is_atari());
+        assert_eq!(white.liberties, vec![(1, 0)]);
+        assert!(!board.group_at((1, 2)).unwrap().is_atari());
+    }
+
+    #[test]
+    fn groups_hold_each_stone_once() {
+        let board = board_from_diagram(&WALLS);
+        assert_eq!(board.groups().len(), 2);
+        let board = board_from_diagram(&[
+            ". . .",
+            "X O X",
+            ". X .",
+        ]);
+        let groups = board.groups();
+        assert_eq!(groups.len(), 4);
+        let mut stones: Vec<(usize,usize)> = groups.iter().flat_map(|group| group.spaces.iter().copied()).collect();
+        stones.sort();
+        stones.dedup();
+        assert_eq!(stones.len(), board.spaces.len());
+    }
+
+    #[test]
+    fn area_score_counts_stones_and_surrounded_regions() {
+        let board = board_from_diagram(&WALLS);
+        assert_eq!(board.area_score(), [10, 15]);
+    }
+
+    #[test]
+    fn area_score_leaves_out_shared_and_empty_regions() {
+        let board = board_from_diagram(&[
+            "X . O",
+            "X . O",
+            "X . O",
+        ]);
+        assert_eq!(board.area_score(), [3, 3]);
+        assert_eq!(board_from_diagram(&[". .", ". ."]).area_score(), [0, 0]);
+    }
+
+    // the stones added and removed by some effects, player turns left out
+    fn piece_changes(effects: &[GameBoardEffect]) -> Vec<(char, usize, (usize,usize))> {
+        effects.iter().filter_map(|effect| match effect.effect {
+            GameBoardEffectType::AddPiece(mv) => Some(('+', mv.player, mv.space)),
+            GameBoardEffectType::RemovePiece(mv) => Some(('-', mv.player, mv.space)),
+            GameBoardEffectType::TogglePlayer => None,
+        }).collect()
+    }
+
+    #[test]
+    fn capture_removes_the_stones_and_counts_prisoners() {
+        let mut board = board_from_diagram(&[
+            ". X . .",
+            "X O X .",
+            ". . . .",
+            ". . . .",
+        ]);
+        let effects = try_move(&mut board, GameBoardMove { player: 0, space: (1, 2) });
+        assert_eq!(piece_changes(&effects), vec![('+', 0, (1, 2)), ('-', 1, (1, 1))]);
+        assert!(!board.spaces.contains_key(&(1, 1)));
+        assert_eq!(board.prisoners, [1, 0]);
+        assert_eq!(board.ko, None);
+    }
+
+    #[test]
+    fn ko_can_not_be_retaken_until_a_pass() {
+        let mut board = board_from_diagram(&[
+            ". X O .",
+            "X O . O",
+            ". X O .",
+            ". . . .",
+        ]);
+        let effects = try_move(&mut board, GameBoardMove { player: 0, space: (2, 1) });
+        assert_eq!(piece_changes(&effects), vec![('+', 0, (2, 1)), ('-', 1, (1, 1))]);
+        assert_eq!(board.ko, Some((1, 1)));
+        let retake = GameBoardMove { player: 1, space: (1, 1) };
+        assert_eq!(check_move(&board, retake), Err(IllegalMove::Ko));
+        assert!(try_move(&mut board, retake).is_empty());
+
+        pass_move(&mut board);
+        assert_eq!(check_move(&board, retake), Ok(()));
+        let effects = try_move(&mut board, retake);
+        assert_eq!(piece_changes(&effects), vec![('+', 1, (1, 1)), ('-', 0, (2, 1))]);
+    }
+
+    #[test]
+    fn suicide_is_illegal_unless_it_captures() {
+        let board = board_from_diagram(&[
+            ". X .",
+            "X . X",
+            ". X .",
+        ]);
+        assert_eq!(check_move(&board, GameBoardMove { player: 1, space: (1, 1) }), Err(IllegalMove::Suicide));
+
+        // the last liberty of both black stones is also the only point left for white
+        let mut board = board_from_diagram(&[
+            ". X O",
+            "X O .",
+            "O . .",
+        ]);
+        assert_eq!(check_move(&board, GameBoardMove { player: 0, space: (0, 0) }), Err(IllegalMove::Suicide));
+        let capture = GameBoardMove { player: 1, space: (0, 0) };
+        assert_eq!(check_move(&board, capture), Ok(()));
+        let mut removed: Vec<_> = piece_changes(&try_move(&mut board, capture)).into_iter().filter(|(change, _, _)| *change == '-').collect();
+        removed.sort();
+        assert_eq!(removed, vec![('-', 0, (0, 1)), ('-', 0, (1, 0))]);
+        assert_eq!(board.prisoners, [0, 2]);
+    }
+
+    #[test]
+    fn set_position_sends_only_the_difference() {
+        let mut board = board_from_diagram(&[
+            "X O .",
+            ". . .",
+            ". . .",
+        ]);
+        board.ko = Some((2, 0));
+        let spaces = HashMap::from([((0, 0), 0), ((1, 0), 0), ((2, 2), 1)]);
+        let effects = set_position(&mut board, spaces.clone());
+        assert_eq!(piece_changes(&effects), vec![('-', 1, (1, 0)), ('+', 0, (1, 0)), ('+', 1, (2, 2))]);
+        assert_eq!(board.spaces, spaces);
+        assert_eq!(board.ko, None);
+    }
+}
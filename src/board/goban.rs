@@ -0,0 +1,159 @@
+use crate::board::{GameBoard, grid_to_world, SPACING};
+use crate::game_state::GameState;
+use bevy::{prelude::*, render::mesh::{Indices, PrimitiveTopology}};
+use bevy_rapier3d::prelude::*;
+
+// wood left around the outer lines, in line spacings
+const GOBAN_MARGIN: f32 = 0.7;
+const GOBAN_THICKNESS: f32 = 0.03;
+const GOBAN_LINE_WIDTH: f32 = 0.0007;
+const GOBAN_STAR_RADIUS: f32 = 0.0016;
+// lines and star points float just above the wood to avoid z-fighting
+const GOBAN_LINE_HEIGHT: f32 = 0.0001;
+
+// width of the board body for a board of `size`, shared by the mesh and the collider
+pub fn goban_width(size: usize) -> f32 {
+    SPACING * ((size - 1) as f32 + 2. * GOBAN_MARGIN)
+}
+
+#[derive(Default, Resource)]
+pub struct GobanState {
+    // board size the current goban entities were built for
+    built_size: usize,
+    pub wood_material: Handle<StandardMaterial>,
+    pub line_material: Handle<StandardMaterial>,
+}
+
+#[derive(Component)]
+pub struct Goban;
+
+pub struct GobanPlugin;
+
+impl Plugin for GobanPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<GobanState>()
+            .add_startup_system(setup_goban)
+            .add_system_set(SystemSet::on_update(GameState::Running)
+                .with_system(update_goban))
+            ;
+    }
+}
+
+fn setup_goban(
+    mut goban: ResMut<GobanState>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    goban.wood_material = materials.add(StandardMaterial {
+        base_color: Color::rgb(0.86, 0.68, 0.42),
+        perceptual_roughness: 0.6,
+        ..default()
+    });
+    goban.line_material = materials.add(StandardMaterial {
+        base_color: Color::rgb(0.08, 0.06, 0.04),
+        unlit: true,
+        ..default()
+    });
+}
+
+// rebuilds the board body, grid and collider whenever the board size changes
+fn update_goban(
+    mut commands: Commands,
+    game_board: Res<GameBoard>,
+    mut goban: ResMut<GobanState>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    goban_query: Query<Entity, With<Goban>>,
+) {
+    if goban.built_size == game_board.size {
+        return;
+    }
+    goban.built_size = game_board.size;
+    for ent in goban_query.iter() {
+        commands.entity(ent).despawn_recursive();
+    }
+
+    let size = game_board.size;
+    let width = goban_width(size);
+    // the top of the body is the board surface at y = 0
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::from(shape::Box::new(width, GOBAN_THICKNESS, width))),
+        material: goban.wood_material.clone(),
+        transform: Transform::from_xyz(0., -GOBAN_THICKNESS / 2., 0.),
+        ..default()
+    })
+    .insert(Goban)
+    .insert(RigidBody::Fixed)
+    .insert(Collider::cuboid(width / 2., GOBAN_THICKNESS / 2., width / 2.));
+
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(grid_mesh(size)),
+        material: goban.line_material.clone(),
+        ..default()
+    }).insert(Goban);
+}
+
+// the traditional star points, corners and centre on 9x9 and up, side stars from 15x15
+pub fn star_points(size: usize) -> Vec<(usize,usize)> {
+    if size < 9 || size.is_multiple_of(2) {
+        return vec![];
+    }
+    let edge = if size >= 13 { 3 } else { 2 };
+    let lines = if size >= 15 { vec![edge, size / 2, size - 1 - edge] } else { vec![edge, size - 1 - edge] };
+    let mut points = vec![];
+    for x in lines.iter() {
+        for y in lines.iter() {
+            points.push((*x, *y));
+        }
+    }
+    if size < 15 {
+        points.push((size / 2, size / 2));
+    }
+    points
+}
+
+// flat quads for every line and small discs for the star points
+fn grid_mesh(size: usize) -> Mesh {
+    let mut positions: Vec<[f32; 3]> = vec![];
+    let mut indices: Vec<u32> = vec![];
+    let last = (size - 1) as f32;
+    let half_line = GOBAN_LINE_WIDTH / 2. / SPACING;
+
+    let mut quad = |corners: [(f32, f32); 4]| {
+        let start = positions.len() as u32;
+        for (x, z) in corners {
+            positions.push((grid_to_world(x, z, size) + Vec3::Y * GOBAN_LINE_HEIGHT).to_array());
+        }
+        indices.extend([start, start + 1, start + 2, start, start + 2, start + 3]);
+    };
+    for i in 0..size {
+        let line = i as f32;
+        // lines running along z, then along x, extended so the corners close
+        quad([(line - half_line, -half_line), (line - half_line, last + half_line),
+            (line + half_line, last + half_line), (line + half_line, -half_line)]);
+        quad([(-half_line, line - half_line), (-half_line, line + half_line),
+            (last + half_line, line + half_line), (last + half_line, line - half_line)]);
+    }
+
+    const STAR_SEGMENTS: u32 = 12;
+    for (x, z) in star_points(size) {
+        let centre = grid_to_world(x as f32, z as f32, size) + Vec3::Y * GOBAN_LINE_HEIGHT * 2.;
+        let start = positions.len() as u32;
+        positions.push(centre.to_array());
+        for segment in 0..STAR_SEGMENTS {
+            let angle = segment as f32 / STAR_SEGMENTS as f32 * std::f32::consts::TAU;
+            positions.push((centre + GOBAN_STAR_RADIUS * Vec3::new(angle.cos(), 0., -angle.sin())).to_array());
+        }
+        for segment in 0..STAR_SEGMENTS {
+            indices.extend([start, start + 1 + segment, start + 1 + (segment + 1) % STAR_SEGMENTS]);
+        }
+    }
+
+    let normals = vec![[0., 1., 0.]; positions.len()];
+    let uvs = vec![[0., 0.]; positions.len()];
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh
+}
@@ -0,0 +1,199 @@
+use crate::board::game_board::{GameBoard, GameBoardMove, try_move};
+
+// give up reading after this many attacker moves, treating the ladder as broken
+const LADDER_READ_BUDGET: usize = 400;
+
+#[derive(Clone, Debug)]
+pub struct LadderReading {
+    // true if the group is captured whatever the defender plays
+    pub captured: bool,
+    // main line of the reading, starting with the defender's move
+    pub path: Vec<GameBoardMove>,
+}
+
+// reads whether the group containing `space`, which must be in atari, dies in a ladder
+// with its owner to move. stones of either colour on the ladder path are taken into account.
+pub fn read_ladder(game_board: &GameBoard, space: (usize,usize)) -> Option<LadderReading> {
+    let group = game_board.group_at(space)?;
+    if !group.is_atari() {
+        return None;
+    }
+
+    let mut budget = LADDER_READ_BUDGET;
+    let mut path = vec![];
+    let captured = read_defender(game_board, space, &mut budget, &mut path);
+    Some(LadderReading { captured, path })
+}
+
+// defender to move with the group in atari, returns true if every defence fails
+fn read_defender(board: &GameBoard, space: (usize,usize), budget: &mut usize, path: &mut Vec<GameBoardMove>) -> bool {
+    let group = board.group_at(space).unwrap();
+    let defender = group.player;
+
+    // extend at the last liberty, or capture an adjacent attacker group that is itself in atari
+    let mut candidates = group.liberties.clone();
+    for stone in group.spaces.iter() {
+        for neighbor in board.neighbors(*stone) {
+            if let Some(attacker_group) = board.group_at(neighbor) {
+                if attacker_group.player != defender && attacker_group.is_atari()
+                    && !candidates.contains(&attacker_group.liberties[0]) {
+                    candidates.push(attacker_group.liberties[0]);
+                }
+            }
+        }
+    }
+
+    let mut capture_line: Option<Vec<GameBoardMove>> = None;
+    for candidate in candidates {
+        let defence = GameBoardMove { player: defender, space: candidate };
+        let mut next = board.clone();
+        if !play_legal(&mut next, defence) {
+            continue;
+        }
+
+        let liberties = next.group_at(space).unwrap().liberties;
+        let mut line = vec![defence];
+        match liberties.len() {
+            0 | 1 => {
+                line.extend(liberties.iter().map(|lib| GameBoardMove { player: (defender + 1) % 2, space: *lib }));
+            }
+            2 => {
+                if !read_attacker(&next, space, budget, &mut line) {
+                    path.extend(line);
+                    return false;
+                }
+            }
+            _ => {
+                path.extend(line);
+                return false;
+            }
+        }
+        if capture_line.is_none() {
+            capture_line = Some(line);
+        }
+    }
+
+    // no legal defence at all means the attacker simply takes the last liberty
+    path.extend(capture_line.unwrap_or_else(|| group.liberties.iter()
+        .map(|lib| GameBoardMove { player: (defender + 1) % 2, space: *lib })
+        .collect::<Vec<_>>()));
+    true
+}
+
+// attacker to move with the group on two liberties, returns true if an atari captures it
+fn read_attacker(board: &GameBoard, space: (usize,usize), budget: &mut usize, path: &mut Vec<GameBoardMove>) -> bool {
+    if *budget == 0 {
+        return false;
+    }
+    *budget -= 1;
+
+    let group = board.group_at(space).unwrap();
+    let attacker = (group.player + 1) % 2;
+    for liberty in group.liberties.iter() {
+        let atari = GameBoardMove { player: attacker, space: *liberty };
+        let mut next = board.clone();
+        if !play_legal(&mut next, atari) {
+            continue;
+        }
+
+        let mut line = vec![atari];
+        if next.group_at(space).is_none_or(|g| g.is_atari())
+            && (!next.spaces.contains_key(&space) || read_defender(&next, space, budget, &mut line)) {
+            path.extend(line);
+            return true;
+        }
+    }
+    false
+}
+
+// plays a move on a scratch board, rejecting occupied points, ko and suicide
+fn play_legal(board: &mut GameBoard, tried_move: GameBoardMove) -> bool {
+    !try_move(board, tried_move).is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::game_board::tests::board_from_diagram;
+
+    const LADDER_START: [&str; 9] = [
+        ". . . . . . . . .",
+        ". . . . . . . . .",
+        ". . X X . . . . .",
+        ". X O . . . . . .",
+        ". . X . . . . . .",
+        ". . . . . . . . .",
+        ". . . . . . . . .",
+        ". . . . . . . . .",
+        ". . . . . . . . .",
+    ];
+
+    #[test]
+    fn ladder_runs_to_the_edge() {
+        let board = board_from_diagram(&LADDER_START);
+        let reading = read_ladder(&board, (2, 3)).unwrap();
+        assert!(reading.captured);
+        assert_eq!(reading.path[0].space, (3, 3));
+        assert_eq!(reading.path[1].space, (4, 3));
+        assert!(reading.path.iter().all(|mv| !board.spaces.contains_key(&mv.space)));
+    }
+
+    #[test]
+    fn ladder_breaker_saves_the_group() {
+        let mut rows = LADDER_START;
+        rows[6] = ". . . . . . O . .";
+        let board = board_from_diagram(&rows);
+        assert!(!read_ladder(&board, (2, 3)).unwrap().captured);
+    }
+
+    #[test]
+    fn attacker_stone_on_path_still_captures() {
+        let mut rows = LADDER_START;
+        rows[6] = ". . . . . . X . .";
+        let board = board_from_diagram(&rows);
+        assert!(read_ladder(&board, (2, 3)).unwrap().captured);
+    }
+
+    #[test]
+    fn capturing_a_surrounding_stone_escapes() {
+        let board = board_from_diagram(&[
+            ". . . . . . . . .",
+            ". . . . . . . . .",
+            ". O X X . . . . .",
+            ". X O . . . . . .",
+            ". O X . . . . . .",
+            ". . . . . . . . .",
+            ". . . . . . . . .",
+            ". . . . . . . . .",
+            ". . . . . . . . .",
+        ]);
+        assert!(!read_ladder(&board, (2, 3)).unwrap().captured);
+    }
+
+    #[test]
+    fn open_extension_is_not_a_ladder() {
+        let board = board_from_diagram(&[
+            ". . . . .",
+            ". . X . .",
+            ". X O X .",
+            ". . . . .",
+            ". . . . .",
+        ]);
+        let reading = read_ladder(&board, (2, 2)).unwrap();
+        assert!(!reading.captured);
+        assert_eq!(reading.path.len(), 1);
+    }
+
+    #[test]
+    fn group_not_in_atari_is_not_read() {
+        let board = board_from_diagram(&[
+            ". . . . .",
+            ". . X . .",
+            ". . O X .",
+            ". . . . .",
+            ". . . . .",
+        ]);
+        assert!(read_ladder(&board, (2, 2)).is_none());
+        assert!(read_ladder(&board, (0, 0)).is_none());
+    }
+}
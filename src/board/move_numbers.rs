@@ -0,0 +1,116 @@
+use crate::actions::KeyInputState;
+use crate::board::{GameBoard, space_to_world, spawn_world_label, SPACING};
+use crate::game_state::GameState;
+use crate::loading::PreLoadingState;
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+// how many of the latest moves the overlay can be limited to, cycled in order
+const MOVE_NUMBER_RANGES: [Option<usize>; 4] = [None, Some(1), Some(5), Some(10)];
+
+// the move number of every stone on the board that was played as a move
+#[derive(Default, Resource)]
+pub struct MoveNumbers {
+    pub numbers: HashMap<(usize,usize),usize>,
+    pub last: usize,
+    // replaces the numbers once the effects of a position change have been applied
+    pending: Option<HashMap<(usize,usize),usize>>,
+    pending_last: usize,
+}
+
+impl MoveNumbers {
+    // for systems that replace the position with set_position, as those stones are not played moves
+    pub fn reset_to(&mut self, numbers: HashMap<(usize,usize),usize>, last: usize) {
+        self.pending = Some(numbers);
+        self.pending_last = last;
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    pub fn apply_pending(&mut self) {
+        if let Some(numbers) = self.pending.take() {
+            self.numbers = numbers;
+            self.last = self.pending_last;
+        }
+    }
+}
+
+#[derive(Default, Resource)]
+pub struct MoveNumberOverlay {
+    pub enabled: bool,
+    // only the last n moves are numbered
+    pub last_n: Option<usize>,
+}
+
+#[derive(Component)]
+pub struct MoveNumberLabel;
+
+pub struct MoveNumbersPlugin;
+
+impl Plugin for MoveNumbersPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<MoveNumbers>()
+            .init_resource::<MoveNumberOverlay>()
+            .add_system_set(SystemSet::on_update(GameState::Running)
+                .with_system(update_move_number_toggles)
+                .with_system(update_move_number_labels.after(update_move_number_toggles)))
+            ;
+    }
+}
+
+fn update_move_number_toggles(
+    key_state: Res<KeyInputState>,
+    mut overlay: ResMut<MoveNumberOverlay>,
+) {
+    if key_state.toggle_move_numbers {
+        overlay.enabled = !overlay.enabled;
+    }
+    if key_state.cycle_move_number_range {
+        let index = MOVE_NUMBER_RANGES.iter().position(|range| *range == overlay.last_n).unwrap_or(0);
+        overlay.last_n = MOVE_NUMBER_RANGES[(index + 1) % MOVE_NUMBER_RANGES.len()];
+    }
+}
+
+// rebuilds the numbers drawn on the stones, dark text on white stones and light text on black ones
+fn update_move_number_labels(
+    mut commands: Commands,
+    overlay: Res<MoveNumberOverlay>,
+    move_numbers: Res<MoveNumbers>,
+    game_board: Res<GameBoard>,
+    pre_loading_state: Res<PreLoadingState>,
+    labels_query: Query<Entity, With<MoveNumberLabel>>,
+) {
+    if !overlay.is_changed() && !move_numbers.is_changed() {
+        return;
+    }
+    for ent in labels_query.iter() {
+        commands.entity(ent).despawn_recursive();
+    }
+    if !overlay.enabled {
+        return;
+    }
+
+    let first = overlay.last_n.map_or(1, |n| (move_numbers.last + 1).saturating_sub(n));
+    for (space, number) in move_numbers.numbers.iter() {
+        if *number < first {
+            continue;
+        }
+        let Some(player) = game_board.spaces.get(space) else {
+            continue;
+        };
+        let label_ent = spawn_world_label(
+            &mut commands,
+            space_to_world(*space, game_board.size) + Vec3::Y * SPACING * 0.5,
+            number.to_string(),
+            TextStyle {
+                font: pre_loading_state.font_handle.clone(),
+                font_size: 16.0,
+                color: if *player == 1 { Color::BLACK } else { Color::WHITE },
+            },
+        );
+        commands.entity(label_ent).insert(MoveNumberLabel);
+    }
+}
@@ -0,0 +1,548 @@
+use crate::board::game_board::{GameBoard, GameBoardEffectType, GameBoardMove, pass_move, try_move};
+use bevy::{
+    asset::{AssetLoader, BoxedFuture, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+};
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::RangeInclusive;
+
+// board sizes that can be played, the coordinate letters run out after 25
+const BOARD_SIZES: RangeInclusive<usize> = 2..=25;
+
+// a single node of an sgf game tree, properties keep their file order
+#[derive(Clone, Debug, Default)]
+pub struct SgfNode {
+    pub properties: Vec<(String, Vec<String>)>,
+    pub parent: Option<usize>,
+    pub children: Vec<usize>,
+}
+
+// one game tree from an sgf collection, stored as an arena with the root at index 0
+#[derive(Clone, Debug, Default)]
+pub struct SgfTree {
+    pub nodes: Vec<SgfNode>,
+}
+
+// all game trees of an sgf file, loaded through the asset server
+#[derive(Debug, TypeUuid)]
+#[uuid = "3c6f1f5e-8f55-4a8c-9d0e-6f2a8a4b5d21"]
+pub struct SgfCollection {
+    pub trees: Vec<SgfTree>,
+}
+
+#[derive(Debug)]
+pub enum SgfError {
+    Empty,
+    UnexpectedEnd,
+    UnexpectedChar(usize, char),
+    BadSize(String),
+}
+
+impl fmt::Display for SgfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SgfError::Empty => write!(f, "no game tree found"),
+            SgfError::UnexpectedEnd => write!(f, "unexpected end of file"),
+            SgfError::UnexpectedChar(pos, c) => write!(f, "unexpected '{}' at byte {}", c, pos),
+            SgfError::BadSize(size) => write!(f, "unsupported board size '{}'", size),
+        }
+    }
+}
+
+impl std::error::Error for SgfError {}
+
+pub struct SgfPlugin;
+
+impl Plugin for SgfPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_asset::<SgfCollection>()
+            .init_asset_loader::<SgfLoader>()
+            ;
+    }
+}
+
+#[derive(Default)]
+pub struct SgfLoader;
+
+impl AssetLoader for SgfLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let trees = parse_sgf(std::str::from_utf8(bytes)?)?;
+            load_context.set_default_asset(LoadedAsset::new(SgfCollection { trees }));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["sgf"]
+    }
+}
+
+impl SgfTree {
+    // a tree holding only a root node for a board of `size`
+    pub fn with_size(size: usize) -> Self {
+        SgfTree {
+            nodes: vec![SgfNode {
+                properties: vec![("SZ".to_string(), vec![size.to_string()])],
+                ..default()
+            }],
+        }
+    }
+
+    pub fn property(&self, node: usize, id: &str) -> Option<&str> {
+        self.properties(node, id).first().map(|v| v.as_str())
+    }
+
+    pub fn properties(&self, node: usize, id: &str) -> &[String] {
+        self.nodes[node].properties.iter()
+            .find(|(prop_id, _)| prop_id == id)
+            .map_or(&[], |(_, values)| values.as_slice())
+    }
+
+    // parsing has checked that SZ is within `BOARD_SIZES`
+    pub fn size(&self) -> usize {
+        self.property(0, "SZ").and_then(|sz| sz.split(':').next()?.trim().parse().ok()).unwrap_or(19)
+    }
+
+    // the colour that moved in `node`, passes included
+    pub fn node_player(&self, node: usize) -> Option<usize> {
+        [("B", 0), ("W", 1)].iter().find(|(id, _)| self.property(node, id).is_some()).map(|(_, player)| *player)
+    }
+
+    // the move played in `node`, passes are ignored
+    pub fn node_move(&self, node: usize) -> Option<GameBoardMove> {
+        [("B", 0), ("W", 1)].iter().find_map(|(id, player)| {
+            let space = parse_point(self.property(node, id)?, self.size())?;
+            Some(GameBoardMove { player: *player, space })
+        })
+    }
+
+    // points changed by the AE, AB and AW setup properties, including compressed point lists,
+    // with the player of the stone put there or none for points AE empties
+    pub fn setup_stones(&self, node: usize) -> Vec<((usize,usize), Option<usize>)> {
+        let mut stones = vec![];
+        for (id, player) in [("AE", None), ("AB", Some(0)), ("AW", Some(1))] {
+            for value in self.properties(node, id) {
+                for space in parse_point_list(value, self.size()) {
+                    stones.push((space, player));
+                }
+            }
+        }
+        stones
+    }
+
+    pub fn child_with_move(&self, node: usize, played: GameBoardMove) -> Option<usize> {
+        self.nodes[node].children.iter().copied().find(|child| {
+            self.node_move(*child).is_some_and(|mv| mv.player == played.player && mv.space == played.space)
+        })
+    }
+
+    // the child of `node` playing `played`, added as a new variation if the tree does not have it yet
+    pub fn add_move(&mut self, node: usize, played: GameBoardMove) -> usize {
+        if let Some(child) = self.child_with_move(node, played) {
+            return child;
+        }
+        let index = self.nodes.len();
+        self.nodes.push(SgfNode {
+            properties: vec![(["B", "W"][played.player].to_string(), vec![point_to_sgf(played.space)])],
+            parent: Some(node),
+            children: vec![],
+        });
+        self.nodes[node].children.push(index);
+        index
+    }
+
+    // the child of `node` where `player` passes, added as a new variation if the tree does not have it yet
+    pub fn add_pass(&mut self, node: usize, player: usize) -> usize {
+        let id = ["B", "W"][player];
+        if let Some(child) = self.nodes[node].children.iter().copied()
+            .find(|child| self.node_player(*child) == Some(player) && self.node_move(*child).is_none()) {
+            return child;
+        }
+        let index = self.nodes.len();
+        self.nodes.push(SgfNode {
+            properties: vec![(id.to_string(), vec![String::new()])],
+            parent: Some(node),
+            children: vec![],
+        });
+        self.nodes[node].children.push(index);
+        index
+    }
+
+    // drops `node` if it is the newest node and has no continuation, as a move taken back right after it was added
+    pub fn remove_last_leaf(&mut self, node: usize) -> bool {
+        if node == 0 || node + 1 != self.nodes.len() || !self.nodes[node].children.is_empty() {
+            return false;
+        }
+        if let Some(parent) = self.nodes.pop().and_then(|removed| removed.parent) {
+            self.nodes[parent].children.retain(|child| *child != node);
+        }
+        true
+    }
+
+    // number of moves played from the root up to and including `node`, passes count as moves
+    pub fn move_number(&self, node: usize) -> usize {
+        self.path_to(node).iter().filter(|node| self.node_player(**node).is_some()).count()
+    }
+
+    // nodes from the root down to `node`
+    pub fn path_to(&self, node: usize) -> Vec<usize> {
+        let mut path = vec![node];
+        while let Some(parent) = self.nodes[*path.last().unwrap()].parent {
+            path.push(parent);
+        }
+        path.reverse();
+        path
+    }
+
+    // the board after playing every setup stone and move on the way to `node`
+    pub fn position_at(&self, node: usize) -> GameBoard {
+        let mut game_board = GameBoard { size: self.size(), ..default() };
+        for path_node in self.path_to(node) {
+            for (space, player) in self.setup_stones(path_node) {
+                match player {
+                    Some(player) => game_board.spaces.insert(space, player),
+                    None => game_board.spaces.remove(&space),
+                };
+            }
+            match self.node_move(path_node) {
+                Some(node_move) => { try_move(&mut game_board, node_move); }
+                None if self.node_player(path_node).is_some() => { pass_move(&mut game_board); }
+                None => {}
+            }
+        }
+        game_board
+    }
+
+    // move numbers of the stones still on the board at `node`
+    pub fn move_numbers(&self, node: usize) -> HashMap<(usize,usize),usize> {
+        let mut game_board = GameBoard { size: self.size(), ..default() };
+        let mut numbers = HashMap::new();
+        let mut number = 0;
+        for path_node in self.path_to(node) {
+            for (space, player) in self.setup_stones(path_node) {
+                match player {
+                    Some(player) => game_board.spaces.insert(space, player),
+                    None => game_board.spaces.remove(&space),
+                };
+                numbers.remove(&space);
+            }
+            if self.node_player(path_node).is_some() {
+                number += 1;
+            }
+            if let Some(node_move) = self.node_move(path_node) {
+                for effect in try_move(&mut game_board, node_move) {
+                    match effect.effect {
+                        GameBoardEffectType::AddPiece(added) => { numbers.insert(added.space, number); }
+                        GameBoardEffectType::RemovePiece(removed) => { numbers.remove(&removed.space); }
+                        GameBoardEffectType::TogglePlayer => {}
+                    }
+                }
+            }
+        }
+        numbers
+    }
+}
+
+// one step of branch navigation through a game tree
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum TreeStep {
+    Back,
+    Forward,
+    Start,
+    End,
+    NextBranch,
+    PreviousBranch,
+}
+
+// a node of a game tree together with the variation that stepping forward follows
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Default)]
+pub struct SgfCursor {
+    pub node: usize,
+    pub branch: usize,
+}
+
+impl SgfCursor {
+    // returns whether the cursor moved to another node or variation
+    pub fn step(&mut self, tree: &SgfTree, step: TreeStep) -> bool {
+        let before = *self;
+        let children = &tree.nodes[self.node].children;
+        match step {
+            TreeStep::Back => if let Some(parent) = tree.nodes[self.node].parent {
+                // stepping forward again returns to the same variation
+                self.branch = tree.nodes[parent].children.iter().position(|child| *child == self.node).unwrap_or(0);
+                self.node = parent;
+            },
+            TreeStep::Forward => if let Some(child) = children.get(self.branch) {
+                *self = SgfCursor { node: *child, branch: 0 };
+            },
+            TreeStep::Start => *self = SgfCursor::default(),
+            TreeStep::End => while self.step(tree, TreeStep::Forward) { },
+            TreeStep::NextBranch => if !children.is_empty() {
+                self.branch = (self.branch + 1) % children.len();
+            },
+            TreeStep::PreviousBranch => if !children.is_empty() {
+                self.branch = (self.branch + children.len() - 1) % children.len();
+            },
+        }
+        *self != before
+    }
+
+    // the colour of the selected continuation, or the opponent of the last move or pass
+    pub fn next_player(&self, tree: &SgfTree) -> usize {
+        let next_player = tree.nodes[self.node].children.get(self.branch).and_then(|child| tree.node_player(*child));
+        match (next_player, tree.node_player(self.node)) {
+            (Some(next_player), _) => next_player,
+            (None, Some(last_player)) => (last_player + 1) % 2,
+            (None, None) => 0,
+        }
+    }
+}
+
+// converts an sgf point such as "dd" to a board space, "" and out of range points are passes
+pub fn parse_point(value: &str, size: usize) -> Option<(usize,usize)> {
+    let bytes = value.as_bytes();
+    if bytes.len() != 2 {
+        return None;
+    }
+    let x = bytes[0].checked_sub(b'a')? as usize;
+    let y = bytes[1].checked_sub(b'a')? as usize;
+    if x < size && y < size { Some((x, y)) } else { None }
+}
+
+pub fn point_to_sgf(space: (usize,usize)) -> String {
+    [space.0, space.1].iter().map(|v| (b'a' + *v as u8) as char).collect()
+}
+
+// a single point or a rectangle written as "aa:cc"
+fn parse_point_list(value: &str, size: usize) -> Vec<(usize,usize)> {
+    let mut corners = value.split(':').filter_map(|p| parse_point(p, size));
+    match (corners.next(), corners.next()) {
+        (Some(a), Some(b)) => {
+            let mut spaces = vec![];
+            for x in a.0.min(b.0)..=a.0.max(b.0) {
+                for y in a.1.min(b.1)..=a.1.max(b.1) {
+                    spaces.push((x, y));
+                }
+            }
+            spaces
+        }
+        (Some(a), None) => vec![a],
+        _ => vec![],
+    }
+}
+
+pub fn parse_sgf(text: &str) -> Result<Vec<SgfTree>, SgfError> {
+    let mut parser = SgfParser { chars: text.char_indices().peekable() };
+    let mut trees = vec![];
+    while parser.skip_whitespace() == Some('(') {
+        let mut tree = SgfTree::default();
+        parser.parse_game_tree(&mut tree, None)?;
+        trees.push(tree);
+    }
+    if let Some((pos, c)) = parser.chars.next() {
+        return Err(SgfError::UnexpectedChar(pos, c));
+    }
+    if trees.is_empty() {
+        return Err(SgfError::Empty);
+    }
+    for tree in trees.iter() {
+        if let Some(sz) = tree.property(0, "SZ") {
+            let size = sz.split(':').next().and_then(|size| size.trim().parse::<usize>().ok());
+            if !size.is_some_and(|size| BOARD_SIZES.contains(&size)) {
+                return Err(SgfError::BadSize(sz.to_string()));
+            }
+        }
+    }
+    Ok(trees)
+}
+
+struct SgfParser<'a> {
+    chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+}
+
+impl<'a> SgfParser<'a> {
+    fn skip_whitespace(&mut self) -> Option<char> {
+        while let Some((_, c)) = self.chars.peek() {
+            if !c.is_whitespace() {
+                return Some(*c);
+            }
+            self.chars.next();
+        }
+        None
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), SgfError> {
+        match self.chars.next() {
+            Some((_, c)) if c == expected => Ok(()),
+            Some((pos, c)) => Err(SgfError::UnexpectedChar(pos, c)),
+            None => Err(SgfError::UnexpectedEnd),
+        }
+    }
+
+    // "(" node+ game_tree* ")", nodes after the first hang off the previous one
+    fn parse_game_tree(&mut self, tree: &mut SgfTree, parent: Option<usize>) -> Result<(), SgfError> {
+        self.skip_whitespace();
+        self.expect('(')?;
+        let mut last = parent;
+        while self.skip_whitespace() == Some(';') {
+            self.chars.next();
+            let index = tree.nodes.len();
+            tree.nodes.push(SgfNode { parent: last, ..default() });
+            if let Some(last) = last {
+                tree.nodes[last].children.push(index);
+            }
+            self.parse_properties(&mut tree.nodes[index])?;
+            last = Some(index);
+        }
+        if last == parent {
+            return match self.chars.next() {
+                Some((pos, c)) => Err(SgfError::UnexpectedChar(pos, c)),
+                None => Err(SgfError::UnexpectedEnd),
+            };
+        }
+        while self.skip_whitespace() == Some('(') {
+            self.parse_game_tree(tree, last)?;
+        }
+        self.skip_whitespace();
+        self.expect(')')
+    }
+
+    fn parse_properties(&mut self, node: &mut SgfNode) -> Result<(), SgfError> {
+        while let Some(c) = self.skip_whitespace() {
+            if !c.is_ascii_uppercase() {
+                break;
+            }
+            let mut id = String::new();
+            while let Some((_, c)) = self.chars.peek() {
+                if !c.is_ascii_alphabetic() {
+                    break;
+                }
+                // old sgf files may use lowercase letters in identifiers, they are ignored
+                if c.is_ascii_uppercase() {
+                    id.push(*c);
+                }
+                self.chars.next();
+            }
+            let mut values = vec![];
+            while self.skip_whitespace() == Some('[') {
+                self.chars.next();
+                values.push(self.parse_value()?);
+            }
+            if values.is_empty() {
+                return match self.chars.next() {
+                    Some((pos, c)) => Err(SgfError::UnexpectedChar(pos, c)),
+                    None => Err(SgfError::UnexpectedEnd),
+                };
+            }
+            node.properties.push((id, values));
+        }
+        Ok(())
+    }
+
+    fn parse_value(&mut self) -> Result<String, SgfError> {
+        let mut value = String::new();
+        loop {
+            match self.chars.next() {
+                Some((_, ']')) => return Ok(value),
+                Some((_, '\\')) => match self.chars.next() {
+                    // escaped line breaks are removed
+                    Some((_, '\n')) => {}
+                    Some((_, c)) => value.push(c),
+                    None => return Err(SgfError::UnexpectedEnd),
+                },
+                Some((_, c)) => value.push(c),
+                None => return Err(SgfError::UnexpectedEnd),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_one(text: &str) -> SgfTree {
+        let mut trees = parse_sgf(text).unwrap();
+        assert_eq!(trees.len(), 1);
+        trees.remove(0)
+    }
+
+    #[test]
+    fn escapes_in_comments() {
+        let tree = parse_one("(;C[a \\] b \\\\ c\\\nd])");
+        assert_eq!(tree.property(0, "C"), Some("a ] b \\ cd"));
+    }
+
+    #[test]
+    fn nested_variations_hang_off_their_node() {
+        let tree = parse_one("(;SZ[9];B[aa](;W[bb];B[cc])(;W[dd](;B[ee])(;B[ff])))");
+        assert_eq!(tree.nodes.len(), 7);
+        assert_eq!(tree.nodes[1].children, vec![2, 4]);
+        assert_eq!(tree.nodes[3].parent, Some(2));
+        assert_eq!(tree.nodes[4].children, vec![5, 6]);
+        let ends: Vec<(usize,usize)> = [5, 6].iter().map(|node| tree.node_move(*node).unwrap().space).collect();
+        assert_eq!(ends, vec![(4, 4), (5, 5)]);
+        assert_eq!(tree.move_number(6), 3);
+    }
+
+    #[test]
+    fn compressed_point_lists() {
+        let tree = parse_one("(;SZ[9]AB[aa:bc][ee]AW[ba:aa])");
+        let stones = tree.setup_stones(0);
+        assert_eq!(stones.iter().filter(|(_, player)| *player == Some(0)).count(), 7);
+        assert_eq!(stones.iter().filter(|(_, player)| *player == Some(1)).count(), 2);
+        assert!(stones.contains(&((1, 2), Some(0))));
+    }
+
+    #[test]
+    fn empty_and_tt_moves_are_passes() {
+        let tree = parse_one("(;SZ[19];B[];W[tt];B[dd])");
+        assert!(tree.node_move(1).is_none());
+        assert!(tree.node_move(2).is_none());
+        assert_eq!(tree.node_move(3).map(|mv| (mv.player, mv.space)), Some((0, (3, 3))));
+    }
+
+    #[test]
+    fn passes_hand_the_turn_over() {
+        let mut tree = SgfTree::with_size(9);
+        let black = tree.add_move(0, GameBoardMove { player: 0, space: (2, 2) });
+        let pass = tree.add_pass(black, 1);
+        assert_eq!(tree.add_pass(black, 1), pass);
+        assert_eq!(SgfCursor { node: pass, branch: 0 }.next_player(&tree), 0);
+        assert_eq!(SgfCursor { node: black, branch: 0 }.next_player(&tree), 1);
+        assert!(tree.remove_last_leaf(pass));
+        assert_eq!(tree.nodes[black].children.len(), 0);
+    }
+
+    #[test]
+    fn ae_clears_setup_stones() {
+        let tree = parse_one("(;SZ[9]AB[aa][bb];AE[aa]AW[cc])");
+        let position = tree.position_at(1);
+        assert_eq!(position.spaces.get(&(0, 0)), None);
+        assert_eq!(position.spaces.get(&(1, 1)), Some(&0));
+        assert_eq!(position.spaces.get(&(2, 2)), Some(&1));
+    }
+
+    #[test]
+    fn malformed_input_is_an_error() {
+        assert!(matches!(parse_sgf(""), Err(SgfError::Empty)));
+        assert!(matches!(parse_sgf("(;B[aa]"), Err(SgfError::UnexpectedEnd)));
+        assert!(matches!(parse_sgf("(;C[open"), Err(SgfError::UnexpectedEnd)));
+        assert!(matches!(parse_sgf("()"), Err(SgfError::UnexpectedChar(1, ')'))));
+        assert!(matches!(parse_sgf("(;B)"), Err(SgfError::UnexpectedChar(3, ')'))));
+        assert!(matches!(parse_sgf("(;B[aa]) x"), Err(SgfError::UnexpectedChar(9, 'x'))));
+        for size in ["0", "1", "26", "nine"] {
+            assert!(matches!(parse_sgf(&format!("(;SZ[{}])", size)), Err(SgfError::BadSize(sz)) if sz == size));
+        }
+        assert!(matches!(parse_sgf("(;SZ[2])(;SZ[40])"), Err(SgfError::BadSize(sz)) if sz == "40"));
+        assert_eq!(parse_sgf("(;SZ[25])").unwrap()[0].size(), 25);
+    }
+}
@@ -0,0 +1,99 @@
+use crate::board::{LidStone, StoneMeshes};
+use crate::game_state::GameState;
+use bevy::prelude::*;
+
+// placed stones fall from this height above their point and bounce once
+const DROP_HEIGHT: f32 = 0.03;
+const DROP_BOUNCE: f32 = 0.002;
+const DROP_SECONDS: f32 = 0.25;
+// share of the drop spent falling, the rest is the bounce
+const DROP_FALL_SHARE: f32 = 0.75;
+// captured stones arc this high on their way to the lid
+const CAPTURE_ARC: f32 = 0.08;
+const CAPTURE_SECONDS: f32 = 0.6;
+
+// a stone falling onto its point, the board already holds it
+#[derive(Component)]
+pub struct StoneDrop {
+    pub rest: Vec3,
+    pub timer: Timer,
+}
+
+impl StoneDrop {
+    pub fn new(rest: Vec3) -> Self {
+        StoneDrop { rest, timer: Timer::from_seconds(DROP_SECONDS, TimerMode::Once) }
+    }
+
+    pub fn start(&self) -> Vec3 {
+        self.rest + Vec3::Y * DROP_HEIGHT
+    }
+}
+
+// a captured stone flying to the lid of `player`, it is no longer part of the board
+#[derive(Component)]
+pub struct StoneCapture {
+    pub player: usize,
+    pub from: Vec3,
+    pub to: Vec3,
+    pub timer: Timer,
+}
+
+impl StoneCapture {
+    pub fn new(player: usize, from: Vec3, to: Vec3) -> Self {
+        StoneCapture { player, from, to, timer: Timer::from_seconds(CAPTURE_SECONDS, TimerMode::Once) }
+    }
+}
+
+pub struct StoneAnimationPlugin;
+
+impl Plugin for StoneAnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_system_set(SystemSet::on_update(GameState::Running)
+                .with_system(update_stone_drops)
+                .with_system(update_stone_captures))
+            ;
+    }
+}
+
+fn update_stone_drops(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut stones_query: Query<(Entity, &mut StoneDrop, &mut Transform)>,
+) {
+    for (ent, mut drop, mut transform) in stones_query.iter_mut() {
+        let t = drop.timer.tick(time.delta()).percent();
+        let height = if t < DROP_FALL_SHARE {
+            let fall = t / DROP_FALL_SHARE;
+            DROP_HEIGHT * (1. - fall * fall)
+        } else {
+            let bounce = (t - DROP_FALL_SHARE) / (1. - DROP_FALL_SHARE);
+            DROP_BOUNCE * 4. * bounce * (1. - bounce)
+        };
+        transform.translation = drop.rest + Vec3::Y * height;
+        if drop.timer.finished() {
+            transform.translation = drop.rest;
+            commands.entity(ent).remove::<StoneDrop>();
+        }
+    }
+}
+
+fn update_stone_captures(
+    mut commands: Commands,
+    time: Res<Time>,
+    stone_meshes: Res<StoneMeshes>,
+    mut stones_query: Query<(Entity, &mut StoneCapture, &mut Transform)>,
+) {
+    for (ent, mut capture, mut transform) in stones_query.iter_mut() {
+        let t = capture.timer.tick(time.delta()).percent();
+        // eases in and out along the way, lifting off first
+        let along = t * t * (3. - 2. * t);
+        transform.translation = capture.from.lerp(capture.to, along) + Vec3::Y * CAPTURE_ARC * 4. * t * (1. - t);
+        // over the lid the stone is let go to fall onto the others
+        if capture.timer.finished() {
+            commands.entity(ent)
+                .remove::<StoneCapture>()
+                .insert(LidStone::body(capture.player, stone_meshes.shape));
+        }
+    }
+}
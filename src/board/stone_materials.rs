@@ -0,0 +1,229 @@
+use bevy::{
+    pbr::StandardMaterialFlags,
+    prelude::*,
+    reflect::TypeUuid,
+    render::{
+        render_asset::RenderAssets,
+        render_resource::{
+            AsBindGroup, AsBindGroupShaderType, Extent3d, ShaderRef, TextureDimension, TextureFormat,
+            TextureViewDescriptor, TextureViewDimension,
+        },
+    },
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+const STONE_TEXTURE_SIZE: u32 = 128;
+// each stone picks one of these, together with a random rotation no two neighbours look alike
+const SHELL_VARIANTS: u64 = 8;
+const SLATE_VARIANTS: u64 = 4;
+// a black cube standing in for the sky while it loads and for environments without one
+pub const NO_ENVIRONMENT: HandleUntyped = HandleUntyped::weak_from_u64(Image::TYPE_UUID, 0x5f0a_73c1_e2b4_4d18);
+
+// the standard pbr lighting plus diffuse and specular light from the sky cubemap
+#[derive(AsBindGroup, TypeUuid, Debug, Clone)]
+#[uuid = "d3b8f2a6-5c41-4e0f-9a7d-2e6b1c8f4a93"]
+#[uniform(0, StoneMaterialUniform)]
+pub struct StoneMaterial {
+    pub base_color: Color,
+    #[texture(1)]
+    #[sampler(2)]
+    pub base_color_texture: Option<Handle<Image>>,
+    pub perceptual_roughness: f32,
+    pub reflectance: f32,
+    // the sky cubemap, set by the theme once it is loaded
+    #[texture(3, dimension = "cube")]
+    #[sampler(4)]
+    pub environment: Handle<Image>,
+    pub environment_intensity: f32,
+    pub alpha_mode: AlphaMode,
+    pub unlit: bool,
+}
+
+impl Default for StoneMaterial {
+    fn default() -> Self {
+        Self {
+            base_color: Color::WHITE,
+            base_color_texture: None,
+            perceptual_roughness: 0.5,
+            reflectance: 0.5,
+            environment: NO_ENVIRONMENT.typed(),
+            environment_intensity: 0.,
+            alpha_mode: AlphaMode::Opaque,
+            unlit: false,
+        }
+    }
+}
+
+impl Material for StoneMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/stone.wgsl".into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        self.alpha_mode
+    }
+}
+
+// the layout checks the ShaderType derive adds count as dead code, so they get a module that allows it
+#[allow(dead_code)]
+mod uniform {
+    use bevy::{prelude::*, render::render_resource::ShaderType};
+
+    #[derive(Clone, Default, ShaderType)]
+    pub struct StoneMaterialUniform {
+        pub base_color: Vec4,
+        pub perceptual_roughness: f32,
+        pub reflectance: f32,
+        // the StandardMaterialFlags bits the pbr functions look at
+        pub flags: u32,
+        pub alpha_cutoff: f32,
+        pub environment_intensity: f32,
+    }
+}
+pub use uniform::StoneMaterialUniform;
+
+impl AsBindGroupShaderType<StoneMaterialUniform> for StoneMaterial {
+    fn as_bind_group_shader_type(&self, _images: &RenderAssets<Image>) -> StoneMaterialUniform {
+        let mut flags = StandardMaterialFlags::NONE;
+        if self.base_color_texture.is_some() {
+            flags |= StandardMaterialFlags::BASE_COLOR_TEXTURE;
+        }
+        if self.unlit {
+            flags |= StandardMaterialFlags::UNLIT;
+        }
+        let mut alpha_cutoff = 0.5;
+        flags |= match self.alpha_mode {
+            AlphaMode::Opaque => StandardMaterialFlags::ALPHA_MODE_OPAQUE,
+            AlphaMode::Mask(cutoff) => {
+                alpha_cutoff = cutoff;
+                StandardMaterialFlags::ALPHA_MODE_MASK
+            }
+            AlphaMode::Blend => StandardMaterialFlags::ALPHA_MODE_BLEND,
+        };
+        StoneMaterialUniform {
+            base_color: self.base_color.as_linear_rgba_f32().into(),
+            perceptual_roughness: self.perceptual_roughness,
+            reflectance: self.reflectance,
+            flags: flags.bits(),
+            alpha_cutoff,
+            environment_intensity: self.environment_intensity,
+        }
+    }
+}
+
+// textured materials for the stones, shell for white and slate for black
+#[derive(Default, Resource)]
+pub struct StoneMaterials {
+    pub shell: Vec<Handle<StoneMaterial>>,
+    pub slate: Vec<Handle<StoneMaterial>>,
+    // kept so a theme can turn the textures off and on again
+    pub shell_textures: Vec<Handle<Image>>,
+    pub slate_textures: Vec<Handle<Image>>,
+}
+
+impl StoneMaterials {
+    pub fn for_player(&self, player: usize) -> &Vec<Handle<StoneMaterial>> {
+        if player == 1 { &self.shell } else { &self.slate }
+    }
+
+    // a random variant and a random turn about the vertical axis for a newly placed stone
+    pub fn random(&self, player: usize) -> (Handle<StoneMaterial>, Quat) {
+        let mut rng = rand::thread_rng();
+        let variants = self.for_player(player);
+        let material = variants[rng.gen_range(0..variants.len())].clone();
+        (material, Quat::from_rotation_y(rng.gen_range(0.0..std::f32::consts::TAU)))
+    }
+}
+
+pub struct StoneMaterialsPlugin;
+
+impl Plugin for StoneMaterialsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_plugin(MaterialPlugin::<StoneMaterial>::default())
+            .init_resource::<StoneMaterials>()
+            .add_startup_system(setup_stone_materials)
+            ;
+    }
+}
+
+fn setup_stone_materials(
+    mut stone_materials: ResMut<StoneMaterials>,
+    mut images: ResMut<Assets<Image>>,
+    mut materials: ResMut<Assets<StoneMaterial>>,
+) {
+    images.set_untracked(NO_ENVIRONMENT, black_cubemap());
+    stone_materials.shell_textures = (0..SHELL_VARIANTS).map(|seed| images.add(shell_texture(seed))).collect();
+    stone_materials.slate_textures = (0..SLATE_VARIANTS).map(|seed| images.add(slate_texture(seed))).collect();
+    stone_materials.shell = stone_materials.shell_textures.iter().map(|texture| materials.add(StoneMaterial {
+        base_color_texture: Some(texture.clone()),
+        perceptual_roughness: 0.35,
+        reflectance: 0.4,
+        ..default()
+    })).collect();
+    stone_materials.slate = stone_materials.slate_textures.iter().map(|texture| materials.add(StoneMaterial {
+        base_color_texture: Some(texture.clone()),
+        perceptual_roughness: 0.85,
+        reflectance: 0.3,
+        ..default()
+    })).collect();
+}
+
+fn black_cubemap() -> Image {
+    let mut image = Image::new(
+        Extent3d { width: 1, height: 1, depth_or_array_layers: 6 },
+        TextureDimension::D2,
+        [0, 0, 0, 255].repeat(6),
+        TextureFormat::Rgba8UnormSrgb,
+    );
+    image.texture_view_descriptor = Some(TextureViewDescriptor {
+        dimension: Some(TextureViewDimension::Cube),
+        ..default()
+    });
+    image
+}
+
+// clamshell white, thin growth bands running across the stone that bend a little towards the edge
+fn shell_texture(seed: u64) -> Image {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let bands = rng.gen_range(18.0..30.0);
+    let bend = rng.gen_range(-0.4..0.4);
+    let phase = rng.gen_range(0.0..1.0);
+    // some bands are stronger than others
+    let strengths: Vec<f32> = (0..64).map(|_| rng.gen_range(0.2..1.0)).collect();
+
+    stone_texture(|u, v| {
+        let across = (u + bend * (v - 0.5) * (v - 0.5)) * bands + phase;
+        let band = strengths[across.floor().rem_euclid(strengths.len() as f32) as usize];
+        let line = (across.fract() - 0.5).abs() * 2.;
+        let shade = 1. - 0.07 * band * line.powf(6.) - 0.015 * rng.gen::<f32>();
+        [0.96 * shade, 0.95 * shade, 0.9 * shade]
+    })
+}
+
+// matte slate black with a faint grain
+fn slate_texture(seed: u64) -> Image {
+    let mut rng = StdRng::seed_from_u64(seed + SHELL_VARIANTS);
+    stone_texture(|_, _| {
+        let grain = rng.gen_range(-0.012..0.012);
+        [0.06 + grain, 0.065 + grain, 0.07 + grain]
+    })
+}
+
+// fills a square srgb texture from a colour for every uv
+fn stone_texture(mut color: impl FnMut(f32, f32) -> [f32; 3]) -> Image {
+    let mut data = Vec::with_capacity((STONE_TEXTURE_SIZE * STONE_TEXTURE_SIZE * 4) as usize);
+    for y in 0..STONE_TEXTURE_SIZE {
+        for x in 0..STONE_TEXTURE_SIZE {
+            let [r, g, b] = color(x as f32 / STONE_TEXTURE_SIZE as f32, y as f32 / STONE_TEXTURE_SIZE as f32);
+            data.extend([r, g, b].map(|c| (c.clamp(0., 1.) * 255.) as u8));
+            data.push(255);
+        }
+    }
+    Image::new(
+        Extent3d { width: STONE_TEXTURE_SIZE, height: STONE_TEXTURE_SIZE, depth_or_array_layers: 1 },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    )
+}
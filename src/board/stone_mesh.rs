@@ -0,0 +1,205 @@
+use crate::actions::{CamerasState, KeyInputState, MouseCamera};
+use crate::game_state::GameState;
+use bevy::{prelude::*, render::mesh::{Indices, PrimitiveTopology}};
+
+pub const STONE_RADIUS: f32 = 0.0072;
+// (segments around, rings per face) of each level of detail, most detailed first
+const STONE_LOD_DETAIL: [(u32, u32); 3] = [(48, 12), (24, 6), (12, 3)];
+// camera distance up to which each level of detail is used
+const STONE_LOD_DISTANCES: [f32; 2] = [0.3, 0.8];
+// the diagram view draws stones as discs this thick, faces barely curved so they shade evenly
+const FLAT_DISC_HEIGHT: f32 = 0.0004;
+const FLAT_DISC_DETAIL: (u32, u32) = (48, 2);
+// level of the flat disc, after the levels of detail
+const FLAT_DISC_LEVEL: usize = STONE_LOD_DETAIL.len();
+
+// a lens shaped stone made of two spherical caps meeting at the rim, the origin is the
+// centre of the bottom face so stones rest on whatever they are placed on
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StoneShape {
+    pub radius: f32,
+    // height of the upper face above the rim
+    pub top: f32,
+    // depth of the lower face below the rim
+    pub bottom: f32,
+}
+
+impl StoneShape {
+    // japanese slate and shell stones, the same curve on both faces
+    pub fn slate_and_shell() -> Self {
+        StoneShape { radius: STONE_RADIUS, top: STONE_RADIUS * 0.42, bottom: STONE_RADIUS * 0.42 }
+    }
+
+    // chinese yunzi, domed on top and nearly flat underneath
+    pub fn yunzi() -> Self {
+        StoneShape { radius: STONE_RADIUS, top: STONE_RADIUS * 0.52, bottom: STONE_RADIUS * 0.1 }
+    }
+
+    pub fn height(&self) -> f32 {
+        self.top + self.bottom
+    }
+
+    // height of the upper face at `distance` from the centre
+    pub fn surface_height(&self, distance: f32) -> f32 {
+        let sphere_radius = cap_sphere_radius(self.radius, self.top);
+        let distance = distance.min(self.radius);
+        self.height() - sphere_radius + (sphere_radius * sphere_radius - distance * distance).sqrt()
+    }
+}
+
+impl Default for StoneShape {
+    fn default() -> Self {
+        StoneShape::slate_and_shell()
+    }
+}
+
+// radius of the sphere whose cap of `height` spans a circle of `radius`
+fn cap_sphere_radius(radius: f32, height: f32) -> f32 {
+    (radius * radius + height * height) / (2. * height)
+}
+
+#[derive(Default, Resource)]
+pub struct StoneMeshes {
+    pub shape: StoneShape,
+    // the levels of detail followed by the flat disc of the diagram view
+    pub lods: Vec<Handle<Mesh>>,
+}
+
+impl StoneMeshes {
+    // the most detailed mesh, for stones that are not switched between levels of detail
+    pub fn mesh(&self) -> Handle<Mesh> {
+        self.lods[0].clone()
+    }
+
+    // replaces the meshes behind the existing handles, so stones already spawned change shape too
+    pub fn set_shape(&mut self, shape: StoneShape, meshes: &mut Assets<Mesh>) {
+        self.shape = shape;
+        let flat = StoneShape { radius: shape.radius, top: FLAT_DISC_HEIGHT / 2., bottom: FLAT_DISC_HEIGHT / 2. };
+        let lods = STONE_LOD_DETAIL.iter().map(|detail| (shape, *detail))
+            .chain(std::iter::once((flat, FLAT_DISC_DETAIL)));
+        if self.lods.is_empty() {
+            self.lods = lods
+                .map(|(shape, (segments, rings))| meshes.add(biconvex_mesh(shape, segments, rings)))
+                .collect();
+        } else {
+            for (lod, (shape, (segments, rings))) in self.lods.iter().zip(lods) {
+                meshes.set_untracked(lod, biconvex_mesh(shape, segments, rings));
+            }
+        }
+    }
+}
+
+// stones on the board, their mesh follows the camera distance or is a flat disc in the diagram view
+#[derive(Component)]
+pub struct StoneLod {
+    pub level: usize,
+}
+
+pub struct StoneMeshPlugin;
+
+impl Plugin for StoneMeshPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<StoneMeshes>()
+            .add_startup_system(setup_stone_meshes)
+            .add_system_set(SystemSet::on_update(GameState::Running)
+                .with_system(update_stone_shape)
+                .with_system(update_stone_lods))
+            ;
+    }
+}
+
+fn setup_stone_meshes(
+    mut stone_meshes: ResMut<StoneMeshes>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    stone_meshes.set_shape(StoneShape::default(), &mut meshes);
+}
+
+fn update_stone_shape(
+    key_state: Res<KeyInputState>,
+    mut stone_meshes: ResMut<StoneMeshes>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    if key_state.toggle_stone_shape {
+        let shape = if stone_meshes.shape == StoneShape::yunzi() { StoneShape::slate_and_shell() } else { StoneShape::yunzi() };
+        stone_meshes.set_shape(shape, &mut meshes);
+    }
+}
+
+fn update_stone_lods(
+    stone_meshes: Res<StoneMeshes>,
+    cameras_state: Res<CamerasState>,
+    cameras_query: Query<&GlobalTransform, With<MouseCamera>>,
+    mut stones_query: Query<(&GlobalTransform, &mut StoneLod, &mut Handle<Mesh>)>,
+) {
+    let Ok(camera_transform) = cameras_query.get_single() else {
+        return;
+    };
+    let camera_position = camera_transform.translation();
+    for (transform, mut lod, mut mesh) in stones_query.iter_mut() {
+        let distance = transform.translation().distance(camera_position);
+        let level = if cameras_state.is_diagram() {
+            FLAT_DISC_LEVEL
+        } else {
+            STONE_LOD_DISTANCES.iter().position(|max| distance <= *max).unwrap_or(STONE_LOD_DISTANCES.len())
+        };
+        if lod.level != level {
+            lod.level = level;
+            *mesh = stone_meshes.lods[level].clone();
+        }
+    }
+}
+
+// both faces are spherical caps, each with its own vertices at the rim so the edge stays sharp,
+// uvs map the stone seen from above onto the unit square
+pub fn biconvex_mesh(shape: StoneShape, segments: u32, rings: u32) -> Mesh {
+    let mut positions: Vec<[f32; 3]> = vec![];
+    let mut normals: Vec<[f32; 3]> = vec![];
+    let mut uvs: Vec<[f32; 2]> = vec![];
+    let mut indices: Vec<u32> = vec![];
+
+    for upper in [true, false] {
+        let height = if upper { shape.top } else { shape.bottom };
+        let sphere_radius = cap_sphere_radius(shape.radius, height);
+        let max_angle = (shape.radius / sphere_radius).min(1.).asin();
+        // the upper sphere is centred below the top, the lower one above the bottom
+        let (centre, facing) = if upper { (shape.height() - sphere_radius, 1.) } else { (sphere_radius, -1.) };
+
+        let start = positions.len() as u32;
+        for ring in 0..=rings {
+            let polar = max_angle * ring as f32 / rings as f32;
+            for segment in 0..=segments {
+                let azimuth = std::f32::consts::TAU * segment as f32 / segments as f32;
+                let normal = Vec3::new(polar.sin() * azimuth.cos(), facing * polar.cos(), polar.sin() * azimuth.sin());
+                let position = Vec3::Y * centre + normal * sphere_radius;
+                positions.push(position.to_array());
+                normals.push(normal.to_array());
+                uvs.push([position.x / shape.radius * 0.5 + 0.5, position.z / shape.radius * 0.5 + 0.5]);
+            }
+        }
+
+        let row = segments + 1;
+        for ring in 0..rings {
+            for segment in 0..segments {
+                let a = start + ring * row + segment;
+                let b = a + row;
+                let c = b + 1;
+                let d = a + 1;
+                // counter clockwise seen from outside the stone
+                if upper {
+                    indices.extend([a, c, b, a, d, c]);
+                } else {
+                    indices.extend([a, b, c, a, c, d]);
+                }
+            }
+        }
+    }
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh
+}
@@ -0,0 +1,354 @@
+use crate::actions::KeyInputState;
+use crate::board::{CubeEnvState, GobanState, NO_ENVIRONMENT, StoneMaterial, StoneMaterials, StoneMeshes, StoneShape};
+use crate::game_state::GameState;
+use crate::loading::PreLoadingState;
+use crate::persistence::{load_data_file, save_data_file};
+use bevy::{
+    asset::{AssetLoader, BoxedFuture, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+};
+use std::fmt;
+
+// themes in the order they are cycled
+const THEME_FILES: [&str; 4] = [
+    "themes/classic_kaya.theme",
+    "themes/modern_flat.theme",
+    "themes/high_contrast.theme",
+    "themes/colour_blind.theme",
+];
+// remembers the chosen theme between runs
+const THEME_CHOICE_FILE: &str = "theme.txt";
+// seconds the name of a newly picked theme stays on screen
+const THEME_TOAST_SECONDS: f32 = 2.0;
+
+// the look of the board, stones, lighting and background, loaded from a .theme file
+#[derive(Clone, Debug, TypeUuid)]
+#[uuid = "8a1d3c52-6b0e-4f7a-b3c9-2e5d7f90a641"]
+pub struct Theme {
+    pub name: String,
+    pub background: Color,
+    pub board: Color,
+    pub board_roughness: f32,
+    pub lines: Color,
+    // plain colours when off, otherwise the stone colours tint the shell and slate textures
+    pub stone_textures: bool,
+    pub white_stone: Color,
+    pub black_stone: Color,
+    pub white_roughness: f32,
+    pub black_roughness: f32,
+    pub stone_shape: StoneShape,
+    pub light: Color,
+    pub light_intensity: f32,
+    pub ambient: Color,
+    pub ambient_brightness: f32,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            name: String::new(),
+            background: Color::rgb(0.4, 0.4, 0.4),
+            board: Color::rgb(0.86, 0.68, 0.42),
+            board_roughness: 0.6,
+            lines: Color::rgb(0.08, 0.06, 0.04),
+            stone_textures: true,
+            white_stone: Color::WHITE,
+            black_stone: Color::WHITE,
+            white_roughness: 0.35,
+            black_roughness: 0.85,
+            stone_shape: StoneShape::default(),
+            light: Color::WHITE,
+            light_intensity: 6000000.,
+            ambient: Color::WHITE,
+            ambient_brightness: 0.05,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ThemeError {
+    BadLine(usize),
+    UnknownKey(usize, String),
+    BadValue(usize, String),
+}
+
+impl fmt::Display for ThemeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThemeError::BadLine(line) => write!(f, "expected `key = value` on line {}", line),
+            ThemeError::UnknownKey(line, key) => write!(f, "unknown key '{}' on line {}", key, line),
+            ThemeError::BadValue(line, key) => write!(f, "bad value for '{}' on line {}", key, line),
+        }
+    }
+}
+
+impl std::error::Error for ThemeError {}
+
+// `key = value` lines, `#` starts a comment, keys that are left out keep their default
+pub fn parse_theme(text: &str) -> Result<Theme, ThemeError> {
+    let mut theme = Theme::default();
+    for (index, line) in text.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(ThemeError::BadLine(line_number));
+        };
+        let (key, value) = (key.trim(), value.trim());
+        let bad_value = || ThemeError::BadValue(line_number, key.to_string());
+        let color = || Color::hex(value).map_err(|_| bad_value());
+        let number = || value.parse::<f32>().map_err(|_| bad_value());
+        match key {
+            "name" => theme.name = value.to_string(),
+            "background" => theme.background = color()?,
+            "board" => theme.board = color()?,
+            "board_roughness" => theme.board_roughness = number()?,
+            "lines" => theme.lines = color()?,
+            "stone_textures" => theme.stone_textures = value.parse().map_err(|_| bad_value())?,
+            "white_stone" => theme.white_stone = color()?,
+            "black_stone" => theme.black_stone = color()?,
+            "white_roughness" => theme.white_roughness = number()?,
+            "black_roughness" => theme.black_roughness = number()?,
+            "stone_shape" => theme.stone_shape = match value {
+                "slate" => StoneShape::slate_and_shell(),
+                "yunzi" => StoneShape::yunzi(),
+                _ => return Err(bad_value()),
+            },
+            "light" => theme.light = color()?,
+            "light_intensity" => theme.light_intensity = number()?,
+            "ambient" => theme.ambient = color()?,
+            "ambient_brightness" => theme.ambient_brightness = number()?,
+            _ => return Err(ThemeError::UnknownKey(line_number, key.to_string())),
+        }
+    }
+    Ok(theme)
+}
+
+#[derive(Default, Resource)]
+pub struct ThemeState {
+    pub themes: Vec<Handle<Theme>>,
+    pub current: usize,
+    // set once the current theme has loaded and been applied
+    applied: bool,
+    // the theme whose stone shape was last used, the player's own shape toggle stays until the theme changes
+    shaped: Option<usize>,
+}
+
+impl ThemeState {
+    // applies the current theme again, for changes to what it is combined with
+    pub fn reapply(&mut self) {
+        self.applied = false;
+    }
+}
+
+#[derive(Component)]
+pub struct ThemeToast {
+    timer: Timer,
+}
+
+pub struct ThemePlugin;
+
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_asset::<Theme>()
+            .init_asset_loader::<ThemeLoader>()
+            .init_resource::<ThemeState>()
+            .add_startup_system(setup_themes)
+            // also runs in the menus so the background follows the theme
+            .add_system(apply_theme)
+            .add_system_set(SystemSet::on_update(GameState::Running)
+                .with_system(update_theme_choice.before(apply_theme))
+                .with_system(update_theme_toast))
+            ;
+    }
+}
+
+#[derive(Default)]
+pub struct ThemeLoader;
+
+impl AssetLoader for ThemeLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let theme = parse_theme(std::str::from_utf8(bytes)?)?;
+            load_context.set_default_asset(LoadedAsset::new(theme));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["theme"]
+    }
+}
+
+fn setup_themes(
+    asset_server: Res<AssetServer>,
+    mut theme_state: ResMut<ThemeState>,
+) {
+    theme_state.themes = THEME_FILES.iter().map(|path| asset_server.load(*path)).collect();
+    let saved = load_data_file(THEME_CHOICE_FILE).unwrap_or_default();
+    theme_state.current = THEME_FILES.iter().position(|path| *path == saved.trim()).unwrap_or(0);
+}
+
+// cycles to the next theme and shows its name for a moment
+fn update_theme_choice(
+    mut commands: Commands,
+    key_state: Res<KeyInputState>,
+    mut theme_state: ResMut<ThemeState>,
+    themes: Res<Assets<Theme>>,
+    pre_loading_state: Res<PreLoadingState>,
+    toasts_query: Query<Entity, With<ThemeToast>>,
+) {
+    if !key_state.cycle_theme {
+        return;
+    }
+    theme_state.current = (theme_state.current + 1) % theme_state.themes.len();
+    theme_state.applied = false;
+    if let Err(err) = save_data_file(THEME_CHOICE_FILE, THEME_FILES[theme_state.current]) {
+        warn!("Could not save theme choice: {}", err);
+    }
+
+    for ent in toasts_query.iter() {
+        commands.entity(ent).despawn_recursive();
+    }
+    let name = themes.get(&theme_state.themes[theme_state.current])
+        .map_or(THEME_FILES[theme_state.current], |theme| theme.name.as_str());
+    commands.spawn(TextBundle {
+        text: Text::from_section(format!("Theme: {}", name), TextStyle {
+            font: pre_loading_state.font_handle.clone(),
+            font_size: 22.0,
+            color: Color::WHITE,
+        }),
+        style: Style {
+            position_type: PositionType::Absolute,
+            position: UiRect { left: Val::Px(12.0), bottom: Val::Px(12.0), ..default() },
+            ..default()
+        },
+        ..default()
+    }).insert(ThemeToast { timer: Timer::from_seconds(THEME_TOAST_SECONDS, TimerMode::Once) });
+}
+
+fn update_theme_toast(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut toasts_query: Query<(Entity, &mut ThemeToast)>,
+) {
+    for (ent, mut toast) in toasts_query.iter_mut() {
+        if toast.timer.tick(time.delta()).finished() {
+            commands.entity(ent).despawn_recursive();
+        }
+    }
+}
+
+// pushes the current theme into the shared materials, so stones and board already spawned change with it
+#[allow(clippy::too_many_arguments)]
+fn apply_theme(
+    mut theme_state: ResMut<ThemeState>,
+    themes: Res<Assets<Theme>>,
+    mut theme_events: EventReader<AssetEvent<Theme>>,
+    goban: Res<GobanState>,
+    cube_env: Res<CubeEnvState>,
+    stone_materials: Res<StoneMaterials>,
+    mut stone_meshes: ResMut<StoneMeshes>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut stone_material_assets: ResMut<Assets<StoneMaterial>>,
+    mut clear_color: ResMut<ClearColor>,
+    mut ambient_light: ResMut<AmbientLight>,
+    mut lights_query: Query<&mut PointLight>,
+    added_lights_query: Query<(), Added<PointLight>>,
+) {
+    let Some(current) = theme_state.themes.get(theme_state.current).cloned() else {
+        return;
+    };
+    // edited theme files are picked up again when the asset server reloads them
+    let reloaded = theme_events.iter().any(|event| matches!(event, AssetEvent::Modified { handle } if *handle == current));
+    if theme_state.applied && !reloaded && added_lights_query.is_empty() {
+        return;
+    }
+    let Some(theme) = themes.get(&current) else {
+        return;
+    };
+    theme_state.applied = true;
+
+    clear_color.0 = theme.background;
+    ambient_light.color = theme.ambient;
+    ambient_light.brightness = theme.ambient_brightness;
+    for mut light in lights_query.iter_mut() {
+        light.color = theme.light;
+        light.intensity = theme.light_intensity;
+    }
+
+    if let Some(wood) = materials.get_mut(&goban.wood_material) {
+        wood.base_color = theme.board;
+        wood.perceptual_roughness = theme.board_roughness;
+    }
+    if let Some(lines) = materials.get_mut(&goban.line_material) {
+        lines.base_color = theme.lines;
+    }
+
+    // the stones are lit by the sky on top of the theme's lights
+    let environment = cube_env.lighting_image();
+    for (handles, textures, color, roughness) in [
+        (&stone_materials.shell, &stone_materials.shell_textures, theme.white_stone, theme.white_roughness),
+        (&stone_materials.slate, &stone_materials.slate_textures, theme.black_stone, theme.black_roughness),
+    ] {
+        for (handle, texture) in handles.iter().zip(textures.iter()) {
+            if let Some(material) = stone_material_assets.get_mut(handle) {
+                material.base_color = color;
+                material.base_color_texture = if theme.stone_textures { Some(texture.clone()) } else { None };
+                material.perceptual_roughness = roughness;
+                material.environment = environment.clone().unwrap_or_else(|| NO_ENVIRONMENT.typed());
+                material.environment_intensity = cube_env.environment().lighting;
+            }
+        }
+    }
+
+    if (reloaded || theme_state.shaped != Some(theme_state.current)) && stone_meshes.shape != theme.stone_shape {
+        stone_meshes.set_shape(theme.stone_shape, &mut meshes);
+    }
+    theme_state.shaped = Some(theme_state.current);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn comments_blank_lines_and_left_out_keys_keep_defaults() {
+        let theme = parse_theme("# night\n\nname = Night # dark\nboard = 202020\nstone_shape = yunzi\n").unwrap();
+        assert_eq!(theme.name, "Night");
+        assert_eq!(theme.board, Color::hex("202020").unwrap());
+        assert_eq!(theme.stone_shape, StoneShape::yunzi());
+        assert_eq!(theme.lines, Theme::default().lines);
+    }
+
+    #[test]
+    fn lines_without_a_value_are_rejected() {
+        assert!(matches!(parse_theme("name = Night\nboard"), Err(ThemeError::BadLine(2))));
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        assert!(matches!(parse_theme("glow = ffffff"), Err(ThemeError::UnknownKey(1, key)) if key == "glow"));
+    }
+
+    #[test]
+    fn bad_values_are_rejected() {
+        for (text, bad_key) in [
+            ("board = wood", "board"),
+            ("light_intensity = bright", "light_intensity"),
+            ("stone_textures = yes", "stone_textures"),
+            ("stone_shape = round", "stone_shape"),
+        ] {
+            assert!(matches!(parse_theme(text), Err(ThemeError::BadValue(1, key)) if key == bad_key), "{}", text);
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use crate::actions::MouseCamera;
+use bevy::prelude::*;
+
+// ui text pinned to a point in the 3d scene, moved every frame to follow the camera
+#[derive(Component)]
+pub struct WorldLabel {
+    pub anchor: Vec3,
+}
+
+pub struct WorldLabelPlugin;
+
+impl Plugin for WorldLabelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_to_stage(CoreStage::PostUpdate, update_world_labels);
+    }
+}
+
+// spawns a centred text label anchored at `anchor`
+pub fn spawn_world_label(
+    commands: &mut Commands,
+    anchor: Vec3,
+    value: String,
+    style: TextStyle,
+) -> Entity {
+    commands.spawn(TextBundle {
+        text: Text::from_section(value, style),
+        style: Style {
+            position_type: PositionType::Absolute,
+            ..default()
+        },
+        visibility: Visibility { is_visible: false },
+        ..default()
+    }).insert(WorldLabel { anchor }).id()
+}
+
+fn update_world_labels(
+    cameras_query: Query<(&Camera, &GlobalTransform), With<MouseCamera>>,
+    mut labels_query: Query<(&WorldLabel, &Node, &mut Style, &mut Visibility)>,
+) {
+    let Ok((camera, camera_transform)) = cameras_query.get_single() else {
+        return;
+    };
+    for (label, node, mut style, mut visibility) in labels_query.iter_mut() {
+        if let Some(screen_pos) = camera.world_to_viewport(camera_transform, label.anchor) {
+            style.position.left = Val::Px(screen_pos.x - node.size().x / 2.);
+            style.position.bottom = Val::Px(screen_pos.y - node.size().y / 2.);
+            visibility.is_visible = true;
+        } else {
+            visibility.is_visible = false;
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use bevy::prelude::*;
+
 // This example game uses States to separate logic
 // See https://bevy-cheatbook.github.io/programming/states.html
 // Or https://github.com/bevyengine/bevy/blob/main/examples/ecs/state.rs
@@ -9,8 +11,27 @@ pub enum GameState {
     Running,
     // Game paused, can resume
     Paused,
+    // Rebinding keys, reached from the menu
+    Controls,
     // Pre loading
     PreLoading,
     // World loading states, level specific assets
+    #[allow(dead_code)]
     WorldLoading,
 }
+
+// What kind of session the Running state plays, chosen from the main menu
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Default, Resource)]
+pub enum GameMode {
+    // Free play on an empty board
+    #[default]
+    Free,
+    // Life and death problems loaded from an sgf problem set
+    Tsumego,
+    // Only the problems the review schedule says are due today
+    DailyReview,
+    // Scripted lessons introducing the rules
+    Tutorial,
+    // Browsing a tree of corner openings
+    Joseki,
+}
@@ -3,13 +3,20 @@ mod audio;
 mod game_state;
 mod loading;
 mod menu;
+mod persistence;
 mod board;
+mod problems;
+mod review;
+mod tutorial;
 
 use crate::actions::ActionsPlugin;
 use crate::audio::InternalAudioPlugin;
-use crate::game_state::GameState;
+use crate::game_state::{GameMode, GameState};
 use crate::menu::MenuPlugin;
 use crate::board::BoardStatePlugin;
+use crate::problems::{ReviewSchedulePlugin, TsumegoPlugin};
+use crate::review::{GameReviewPlugin, JosekiPlugin};
+use crate::tutorial::TutorialPlugin;
 
 use bevy::app::App;
 #[cfg(debug_assertions)]
@@ -20,14 +27,20 @@ pub struct GamePlugin;
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
         app.add_state(GameState::PreLoading)
+            .init_resource::<GameMode>()
             .add_plugin(MenuPlugin)
             .add_plugin(ActionsPlugin)
             .add_plugin(InternalAudioPlugin)
-            .add_plugin(BoardStatePlugin);
+            .add_plugin(BoardStatePlugin)
+            .add_plugin(ReviewSchedulePlugin)
+            .add_plugin(TsumegoPlugin)
+            .add_plugin(TutorialPlugin)
+            .add_plugin(JosekiPlugin)
+            .add_plugin(GameReviewPlugin);
 
         #[cfg(debug_assertions)]
         {
-            app.add_plugin(FrameTimeDiagnosticsPlugin::default())
+            app.add_plugin(FrameTimeDiagnosticsPlugin)
                 .add_plugin(LogDiagnosticsPlugin::default());
         }
     }
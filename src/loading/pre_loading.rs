@@ -9,7 +9,6 @@ pub struct PreLoadingPlugin;
 pub struct PreLoadingState {
     pub pre_loaded: bool,
     pub font_handle: Handle<Font>,
-    pub ui_entity: Option<Entity>,
     pub sound_01: Handle<AudioSource>,
     pub sound_02: Handle<AudioSource>,
 }
@@ -32,13 +31,12 @@ fn setup_pre_loading(
     asset_server: Res<AssetServer>,
 ) {
     // Camera
-    commands.spawn_bundle(Camera3dBundle {
+    commands.spawn(Camera3dBundle {
         transform: Transform::from_xyz(0.0, 0.3, 0.2),
         ..Default::default()
     })
     .insert(UiCameraConfig {
         show_ui: true,
-        ..default()
     })
     .insert(MouseCamera::default());
 
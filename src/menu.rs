@@ -1,10 +1,13 @@
 use crate::loading::{PreLoadingPlugin,PreLoadingState};
-use crate::GameState;
+use crate::game_state::{GameMode, GameState};
+use crate::problems::{ReviewSchedule, review_today};
 use bevy::prelude::*;
 
 
 mod button_colors;
 pub use button_colors::*;
+mod controls_menu;
+pub use controls_menu::*;
 mod pause_menu;
 pub use pause_menu::*;
 
@@ -19,22 +22,70 @@ impl Plugin for MenuPlugin {
             .init_resource::<ButtonColors>()
             .add_plugin(PreLoadingPlugin)
             .add_plugin(PauseMenuStatePlugin)
+            .add_plugin(ControlsMenuPlugin)
             .add_system_set(SystemSet::on_enter(GameState::Menu).with_system(setup_menu))
             .add_system_set(SystemSet::on_update(GameState::Menu).with_system(click_play_button))
             .add_system_set(SystemSet::on_exit(GameState::Menu).with_system(cleanup_menu));
     }
 }
 
+// what a main menu button does, start a game mode or open the controls
+#[derive(Clone, Copy, Component)]
+pub enum MenuButton {
+    Play(GameMode),
+    Controls,
+}
+
+// root node of the main menu, despawned with all its buttons
+#[derive(Component)]
+pub struct MenuRoot;
+
 fn setup_menu(
     mut commands: Commands,
     pre_loading_state: Res<PreLoadingState>,
     button_colors: Res<ButtonColors>,
+    review_schedule: Res<ReviewSchedule>,
 ) {
+    let review_label = format!("Daily review ({})", review_schedule.due_count(review_today()));
     commands
+        .spawn(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            background_color: Color::NONE.into(),
+            ..Default::default()
+        })
+        .insert(MenuRoot)
+        .with_children(|parent| {
+            for (label, button) in [
+                ("Play", MenuButton::Play(GameMode::Free)),
+                ("Tutorial", MenuButton::Play(GameMode::Tutorial)),
+                ("Problems", MenuButton::Play(GameMode::Tsumego)),
+                ("Joseki", MenuButton::Play(GameMode::Joseki)),
+                (review_label.as_str(), MenuButton::Play(GameMode::DailyReview)),
+                ("Controls", MenuButton::Controls),
+            ] {
+                spawn_menu_button(parent, &pre_loading_state, &button_colors, label, button);
+            }
+        });
+}
+
+fn spawn_menu_button(
+    parent: &mut ChildBuilder,
+    pre_loading_state: &PreLoadingState,
+    button_colors: &ButtonColors,
+    label: &str,
+    button: MenuButton,
+) {
+    parent
         .spawn(ButtonBundle {
             style: Style {
-                size: Size::new(Val::Px(120.0), Val::Px(50.0)),
-                margin: UiRect::all(Val::Auto),
+                size: Size::new(Val::Px(320.0), Val::Px(50.0)),
+                margin: UiRect::all(Val::Px(8.0)),
                 justify_content: JustifyContent::Center,
                 align_items: AlignItems::Center,
                 ..Default::default()
@@ -42,11 +93,12 @@ fn setup_menu(
             background_color: button_colors.normal.into(),
             ..Default::default()
         })
+        .insert(button)
         .with_children(|parent| {
             parent.spawn(TextBundle {
                 text: Text {
                     sections: vec![TextSection {
-                        value: "Play".to_string(),
+                        value: label.to_string(),
                         style: TextStyle {
                             font: pre_loading_state.font_handle.clone(),
                             font_size: 40.0,
@@ -60,19 +112,25 @@ fn setup_menu(
         });
 }
 
+#[allow(clippy::type_complexity)]
 fn click_play_button(
     button_colors: Res<ButtonColors>,
     mut state: ResMut<State<GameState>>,
+    mut game_mode: ResMut<GameMode>,
     mut interaction_query: Query<
-        (&Interaction, &mut BackgroundColor),
+        (&Interaction, &mut BackgroundColor, &MenuButton),
         (Changed<Interaction>, With<Button>),
     >,
 ) {
-    for (interaction, mut color) in &mut interaction_query {
+    for (interaction, mut color, menu_button) in &mut interaction_query {
         match *interaction {
-            Interaction::Clicked => {
-                state.set(GameState::Running).unwrap();
-            }
+            Interaction::Clicked => match *menu_button {
+                MenuButton::Play(mode) => {
+                    *game_mode = mode;
+                    state.set(GameState::Running).unwrap();
+                }
+                MenuButton::Controls => state.set(GameState::Controls).unwrap(),
+            },
             Interaction::Hovered => {
                 *color = button_colors.hovered.into();
             }
@@ -83,6 +141,6 @@ fn click_play_button(
     }
 }
 
-fn cleanup_menu(mut commands: Commands, button: Query<Entity, With<Button>>) {
-    commands.entity(button.single()).despawn_recursive();
+fn cleanup_menu(mut commands: Commands, menu_root: Query<Entity, With<MenuRoot>>) {
+    commands.entity(menu_root.single()).despawn_recursive();
 }
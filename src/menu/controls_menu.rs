@@ -0,0 +1,255 @@
+use crate::actions::{KeyInputMap, is_bindable, key_name, save_keymap};
+use crate::game_state::GameState;
+use crate::loading::PreLoadingState;
+use crate::menu::ButtonColors;
+use bevy::prelude::*;
+
+// rows of bindings in each column of the list
+const BINDING_ROWS: f32 = 18.;
+const BINDING_ROW_HEIGHT: f32 = 28.;
+
+// system state
+#[derive(Default, Resource)]
+pub struct ControlsMenuState {
+    pub ui_entity: Option<Entity>,
+    // the binding waiting for its new key
+    pub listening: Option<&'static str>,
+    pub message: String,
+}
+
+// a row of the list, clicked to rebind the action `name`
+#[derive(Component)]
+pub struct BindingButton {
+    name: &'static str,
+}
+
+#[derive(Component)]
+pub struct BindingKeyText {
+    name: &'static str,
+}
+
+#[derive(Component)]
+pub struct ControlsMessage;
+
+#[derive(Clone, Copy, Component)]
+pub enum ControlsButton {
+    Defaults,
+    Back,
+}
+
+// plugin
+pub struct ControlsMenuPlugin;
+
+impl Plugin for ControlsMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app
+        .init_resource::<ControlsMenuState>()
+        .add_system_set(SystemSet::on_enter(GameState::Controls)
+            .with_system(enter_controls_menu))
+        .add_system_set(SystemSet::on_update(GameState::Controls)
+            .with_system(click_controls_buttons)
+            .with_system(update_listening.after(click_controls_buttons))
+            .with_system(update_controls_texts.after(update_listening)))
+        .add_system_set(SystemSet::on_exit(GameState::Controls)
+            .with_system(exit_controls_menu))
+        ;
+    }
+}
+
+fn enter_controls_menu(
+    mut commands: Commands,
+    mut controls_menu: ResMut<ControlsMenuState>,
+    pre_loading_state: Res<PreLoadingState>,
+    button_colors: Res<ButtonColors>,
+    keymap: Res<KeyInputMap>,
+) {
+    let text_style = |font_size: f32| TextStyle {
+        font: pre_loading_state.font_handle.clone(),
+        font_size,
+        color: Color::rgb(0.9, 0.9, 0.9),
+    };
+    controls_menu.listening = None;
+    controls_menu.message = "Click an action, then press its new key".to_string();
+    controls_menu.ui_entity = Some(commands
+        .spawn(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: Color::NONE.into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section("Controls", text_style(40.0)));
+
+            // the bindings run down one column and continue in the next
+            parent.spawn(NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Undefined, Val::Px(BINDING_ROWS * (BINDING_ROW_HEIGHT + 2.0))),
+                    flex_direction: FlexDirection::Column,
+                    flex_wrap: FlexWrap::Wrap,
+                    margin: UiRect::all(Val::Px(12.0)),
+                    ..default()
+                },
+                background_color: Color::NONE.into(),
+                ..default()
+            }).with_children(|parent| {
+                for (name, label, key) in keymap.bindings() {
+                    parent.spawn(ButtonBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(380.0), Val::Px(BINDING_ROW_HEIGHT)),
+                            margin: UiRect::all(Val::Px(1.0)),
+                            padding: UiRect::horizontal(Val::Px(8.0)),
+                            justify_content: JustifyContent::SpaceBetween,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        background_color: button_colors.normal.into(),
+                        ..default()
+                    })
+                    .insert(BindingButton { name })
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(label, text_style(18.0)));
+                        parent.spawn(TextBundle::from_section(key_name(key), text_style(18.0)))
+                            .insert(BindingKeyText { name });
+                    });
+                }
+            });
+
+            parent.spawn(TextBundle::from_section(controls_menu.message.clone(), text_style(22.0)))
+                .insert(ControlsMessage);
+
+            parent.spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    ..default()
+                },
+                background_color: Color::NONE.into(),
+                ..default()
+            }).with_children(|parent| {
+                for (label, button) in [("Defaults", ControlsButton::Defaults), ("Back", ControlsButton::Back)] {
+                    parent.spawn(ButtonBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(160.0), Val::Px(50.0)),
+                            margin: UiRect::all(Val::Px(8.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        background_color: button_colors.normal.into(),
+                        ..default()
+                    })
+                    .insert(button)
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(label, text_style(32.0)));
+                    });
+                }
+            });
+        }).id());
+}
+
+#[allow(clippy::type_complexity)]
+fn click_controls_buttons(
+    button_colors: Res<ButtonColors>,
+    mut state: ResMut<State<GameState>>,
+    mut controls_menu: ResMut<ControlsMenuState>,
+    mut keymap: ResMut<KeyInputMap>,
+    mut interaction_query: Query<
+        (&Interaction, &mut BackgroundColor, Option<&BindingButton>, Option<&ControlsButton>),
+        (Changed<Interaction>, With<Button>),
+    >,
+) {
+    for (interaction, mut color, binding, button) in interaction_query.iter_mut() {
+        match *interaction {
+            Interaction::Clicked => match (binding, button) {
+                (Some(binding), _) => {
+                    controls_menu.listening = Some(binding.name);
+                    controls_menu.message = "Press a key, Esc to cancel".to_string();
+                }
+                (_, Some(ControlsButton::Defaults)) => {
+                    *keymap = KeyInputMap::default();
+                    save_keymap(&keymap);
+                    controls_menu.listening = None;
+                    controls_menu.message = "Default keys restored".to_string();
+                }
+                (_, Some(ControlsButton::Back)) => state.set(GameState::Menu).unwrap(),
+                (None, None) => {}
+            },
+            Interaction::Hovered => {
+                *color = button_colors.hovered.into();
+            }
+            Interaction::None => {
+                *color = button_colors.normal.into();
+            }
+        }
+    }
+}
+
+// binds the next key pressed to the action being changed, unless another action already uses it
+fn update_listening(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut state: ResMut<State<GameState>>,
+    mut controls_menu: ResMut<ControlsMenuState>,
+    mut keymap: ResMut<KeyInputMap>,
+) {
+    let Some(key) = keyboard_input.get_just_pressed().next().copied() else {
+        return;
+    };
+    let Some(name) = controls_menu.listening else {
+        if key == KeyCode::Escape {
+            state.set(GameState::Menu).unwrap();
+        }
+        return;
+    };
+    controls_menu.listening = None;
+    controls_menu.message = if key == KeyCode::Escape {
+        String::new()
+    } else if !is_bindable(key) {
+        format!("{} can not be bound", key_name(key))
+    } else if let Some(other) = keymap.conflict(name, key) {
+        format!("{} is already used by {}", key_name(key), other)
+    } else {
+        let mut bindings = keymap.bindings_mut();
+        if let Some((_, _, bound)) = bindings.iter_mut().find(|(other, _, _)| *other == name) {
+            **bound = key;
+        }
+        save_keymap(&keymap);
+        String::new()
+    };
+}
+
+fn update_controls_texts(
+    controls_menu: Res<ControlsMenuState>,
+    keymap: Res<KeyInputMap>,
+    mut key_texts_query: Query<(&mut Text, &BindingKeyText), Without<ControlsMessage>>,
+    mut message_query: Query<&mut Text, With<ControlsMessage>>,
+) {
+    if !controls_menu.is_changed() && !keymap.is_changed() {
+        return;
+    }
+    let bindings = keymap.bindings();
+    for (mut text, key_text) in key_texts_query.iter_mut() {
+        text.sections[0].value = if controls_menu.listening == Some(key_text.name) {
+            "...".to_string()
+        } else {
+            bindings.iter().find(|(name, _, _)| *name == key_text.name)
+                .map_or(String::new(), |(_, _, key)| key_name(*key))
+        };
+    }
+    for mut text in message_query.iter_mut() {
+        text.sections[0].value = controls_menu.message.clone();
+    }
+}
+
+fn exit_controls_menu(
+    mut commands: Commands,
+    mut controls_menu: ResMut<ControlsMenuState>,
+) {
+    // despawn ui
+    if let Some(ui_entity) = controls_menu.ui_entity.take() {
+        commands.entity(ui_entity).despawn_recursive();
+    }
+}
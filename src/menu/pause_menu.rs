@@ -8,6 +8,10 @@ use bevy::{prelude::*, window::CursorGrabMode};
 #[derive(Default, Resource)]
 pub struct PauseMenuState {
     pub ui_entity: Option<Entity>,
+    // the cursor was locked before pausing and is locked again on resume
+    pub relock: bool,
+    // the press that paused may still be seen right after the menu opens
+    gamepad_ready: bool,
 }
 
 // plugin
@@ -21,7 +25,8 @@ impl Plugin for PauseMenuStatePlugin {
         .add_system_set(SystemSet::on_enter(GameState::Paused)
             .with_system(enter_pause_menu))
         .add_system_set(SystemSet::on_update(GameState::Paused)
-            .with_system(click_play_button))
+            .with_system(click_play_button)
+            .with_system(gamepad_resume))
         .add_system_set(SystemSet::on_exit(GameState::Paused)
             .with_system(exit_pause_menu))
         ;
@@ -38,7 +43,7 @@ fn enter_pause_menu(
 ) {
     // pause menu ui
     pause_menu_state.ui_entity = Some(commands
-        .spawn_bundle(ButtonBundle {
+        .spawn(ButtonBundle {
             style: Style {
                 size: Size::new(Val::Px(160.0), Val::Px(50.0)),
                 margin: UiRect::all(Val::Auto),
@@ -50,7 +55,7 @@ fn enter_pause_menu(
             ..Default::default()
         })
         .with_children(|parent| {
-            parent.spawn_bundle(TextBundle {
+            parent.spawn(TextBundle {
                 text: Text {
                     sections: vec![TextSection {
                         value: "Resume".to_string(),
@@ -68,6 +73,8 @@ fn enter_pause_menu(
 
     // exit cursor lock
     let window = windows.get_primary_mut().unwrap();
+    pause_menu_state.relock = cursor_lock_controls.enabled;
+    pause_menu_state.gamepad_ready = false;
     if window.cursor_grab_mode() != CursorGrabMode::None {
         window.set_cursor_grab_mode(CursorGrabMode::None);
         window.set_cursor_visibility(true);
@@ -75,8 +82,10 @@ fn enter_pause_menu(
     }
 }
 
+#[allow(clippy::type_complexity)]
 fn click_play_button(
     button_colors: Res<ButtonColors>,
+    pause_menu: Res<PauseMenuState>,
     mut state: ResMut<State<GameState>>,
     mut interaction_query: Query<
         (&Interaction, &mut BackgroundColor),
@@ -88,12 +97,7 @@ fn click_play_button(
     for (interaction, mut color) in interaction_query.iter_mut() {
         match *interaction {
             Interaction::Clicked => {
-                state.set(GameState::Running).unwrap();
-                // request cursor lock
-                let window = windows.get_primary_mut().unwrap();
-                window.set_cursor_grab_mode(CursorGrabMode::Locked);
-                window.set_cursor_visibility(false);
-                cursor_lock_controls.enabled = true;
+                resume(&pause_menu, &mut state, &mut cursor_lock_controls, &mut windows);
             }
             Interaction::Hovered => {
                 *color = button_colors.hovered.into();
@@ -105,6 +109,46 @@ fn click_play_button(
     }
 }
 
+// start or the play button on a gamepad resumes
+fn gamepad_resume(
+    mut pause_menu: ResMut<PauseMenuState>,
+    gamepads: Res<Gamepads>,
+    button_input: Res<Input<GamepadButton>>,
+    mut state: ResMut<State<GameState>>,
+    mut cursor_lock_controls: ResMut<CursorLockState>,
+    mut windows: ResMut<Windows>,
+) {
+    if !pause_menu.gamepad_ready {
+        pause_menu.gamepad_ready = true;
+        return;
+    }
+    let pressed = gamepads.iter().any(|gamepad| {
+        button_input.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::Start))
+            || button_input.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::South))
+    });
+    if pressed {
+        resume(&pause_menu, &mut state, &mut cursor_lock_controls, &mut windows);
+    }
+}
+
+fn resume(
+    pause_menu: &PauseMenuState,
+    state: &mut State<GameState>,
+    cursor_lock_controls: &mut CursorLockState,
+    windows: &mut Windows,
+) {
+    if state.pop().is_err() {
+        return;
+    }
+    if pause_menu.relock {
+        // request cursor lock
+        let window = windows.get_primary_mut().unwrap();
+        window.set_cursor_grab_mode(CursorGrabMode::Locked);
+        window.set_cursor_visibility(false);
+        cursor_lock_controls.enabled = true;
+    }
+}
+
 fn exit_pause_menu(
     mut commands: Commands,
     pause_menu: Res<PauseMenuState>,
@@ -0,0 +1,51 @@
+// small text files kept between runs in the user's data directory
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load_data_file(name: &str) -> Option<String> {
+    std::fs::read_to_string(data_dir().join(name)).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save_data_file(name: &str, contents: &str) -> std::io::Result<()> {
+    let dir = data_dir();
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(dir.join(name), contents)
+}
+
+// the web build has no file system, files are kept in the page's local storage instead
+#[cfg(target_arch = "wasm32")]
+pub fn load_data_file(name: &str) -> Option<String> {
+    local_storage()?.get_item(&storage_key(name)).ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn save_data_file(name: &str, contents: &str) -> std::io::Result<()> {
+    let storage = local_storage().ok_or_else(|| std::io::Error::other("no local storage"))?;
+    storage.set_item(&storage_key(name), contents)
+        .map_err(|err| std::io::Error::other(format!("{:?}", err)))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn storage_key(name: &str) -> String {
+    format!("gobanb/{}", name)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn data_dir() -> PathBuf {
+    let base = if cfg!(target_os = "windows") {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else {
+        std::env::var_os("XDG_DATA_HOME").map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+    };
+    base.unwrap_or_else(|| PathBuf::from(".")).join("gobanb")
+}
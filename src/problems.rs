@@ -0,0 +1,4 @@
+mod review_schedule;
+pub use review_schedule::*;
+mod tsumego;
+pub use tsumego::*;
@@ -0,0 +1,182 @@
+use crate::persistence::{load_data_file, save_data_file};
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+const REVIEW_SCHEDULE_FILE: &str = "reviews.txt";
+// sm-2 starting and minimum ease factors
+const REVIEW_INITIAL_EASE: f32 = 2.5;
+const REVIEW_MIN_EASE: f32 = 1.3;
+
+// sm-2 state of one problem, days are counted from the unix epoch
+#[derive(Clone, Copy, Debug)]
+pub struct ReviewEntry {
+    pub ease: f32,
+    pub interval: u32,
+    pub repetitions: u32,
+    pub due_day: u64,
+    pub attempts: u32,
+    pub failures: u32,
+}
+
+impl Default for ReviewEntry {
+    fn default() -> Self {
+        Self {
+            ease: REVIEW_INITIAL_EASE,
+            interval: 0,
+            repetitions: 0,
+            due_day: 0,
+            attempts: 0,
+            failures: 0,
+        }
+    }
+}
+
+impl ReviewEntry {
+    // applies an answer graded from 0 (blackout) to 5 (perfect) on `today`
+    pub fn review(&mut self, quality: u32, today: u64) {
+        let quality = quality.min(5);
+        self.attempts += 1;
+        if quality >= 3 {
+            self.interval = match self.repetitions {
+                0 => 1,
+                1 => 6,
+                _ => (self.interval as f32 * self.ease).round() as u32,
+            };
+            self.repetitions += 1;
+        } else {
+            self.failures += 1;
+            self.repetitions = 0;
+            self.interval = 1;
+        }
+        let miss = (5 - quality) as f32;
+        self.ease = (self.ease + 0.1 - miss * (0.08 + miss * 0.02)).max(REVIEW_MIN_EASE);
+        self.due_day = today.saturating_add(self.interval as u64);
+    }
+}
+
+// review state of every problem attempted so far, keyed by problem id
+#[derive(Default, Resource)]
+pub struct ReviewSchedule {
+    pub entries: HashMap<String, ReviewEntry>,
+}
+
+impl ReviewSchedule {
+    pub fn is_due(&self, problem_id: &str, today: u64) -> bool {
+        self.entries.get(problem_id).is_some_and(|entry| entry.due_day <= today)
+    }
+
+    pub fn due_count(&self, today: u64) -> usize {
+        self.entries.values().filter(|entry| entry.due_day <= today).count()
+    }
+
+    pub fn record_attempt(&mut self, problem_id: &str, solved: bool, today: u64) {
+        let entry = self.entries.entry(problem_id.to_string()).or_default();
+        entry.review(if solved { 4 } else { 1 }, today);
+        self.save();
+    }
+
+    // one tab separated line per problem
+    fn load() -> Self {
+        let mut schedule = ReviewSchedule::default();
+        for line in load_data_file(REVIEW_SCHEDULE_FILE).unwrap_or_default().lines() {
+            let fields: Vec<&str> = line.split('\t').collect();
+            if let [id, ease, interval, repetitions, due_day, attempts, failures] = fields[..] {
+                let entry = (|| Some(ReviewEntry {
+                    ease: ease.parse().ok()?,
+                    interval: interval.parse().ok()?,
+                    repetitions: repetitions.parse().ok()?,
+                    due_day: due_day.parse().ok()?,
+                    attempts: attempts.parse().ok()?,
+                    failures: failures.parse().ok()?,
+                }))();
+                match entry {
+                    Some(entry) => { schedule.entries.insert(id.to_string(), entry); }
+                    None => warn!("Skipping bad review line: {}", line),
+                }
+            }
+        }
+        schedule
+    }
+
+    fn save(&self) {
+        let mut ids: Vec<&String> = self.entries.keys().collect();
+        ids.sort();
+        let mut contents = String::new();
+        for id in ids {
+            let e = &self.entries[id];
+            contents += &format!("{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+                id, e.ease, e.interval, e.repetitions, e.due_day, e.attempts, e.failures);
+        }
+        if let Err(err) = save_data_file(REVIEW_SCHEDULE_FILE, &contents) {
+            warn!("Could not save review schedule: {}", err);
+        }
+    }
+}
+
+pub struct ReviewSchedulePlugin;
+
+impl Plugin for ReviewSchedulePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ReviewSchedule::load());
+    }
+}
+
+// days since the unix epoch
+#[cfg(not(target_arch = "wasm32"))]
+pub fn review_today() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() / 86400)
+}
+
+// the browser has no system time in std, its clock counts milliseconds
+#[cfg(target_arch = "wasm32")]
+pub fn review_today() -> u64 {
+    (js_sys::Date::now() / 86_400_000.) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intervals_grow_from_one_and_six_days_by_the_ease() {
+        let mut entry = ReviewEntry::default();
+        entry.review(5, 100);
+        assert_eq!((entry.interval, entry.due_day), (1, 101));
+        entry.review(5, 101);
+        assert_eq!((entry.interval, entry.due_day), (6, 107));
+        // the third interval uses the ease earned so far, a perfect answer raises it by 0.1
+        let ease = entry.ease;
+        assert!((ease - 2.7).abs() < 1e-4);
+        entry.review(5, 107);
+        assert_eq!(entry.interval, (6. * ease).round() as u32);
+        assert!((entry.ease - 2.8).abs() < 1e-4);
+        assert_eq!(entry.repetitions, 3);
+    }
+
+    #[test]
+    fn ease_stops_at_its_floor() {
+        let mut entry = ReviewEntry::default();
+        for day in 0..10 {
+            entry.review(3, day);
+        }
+        assert_eq!(entry.ease, REVIEW_MIN_EASE);
+        entry.review(0, 10);
+        assert_eq!(entry.ease, REVIEW_MIN_EASE);
+    }
+
+    #[test]
+    fn failed_answers_start_over() {
+        let mut entry = ReviewEntry::default();
+        entry.review(4, 0);
+        entry.review(4, 1);
+        entry.review(4, 7);
+        assert!(entry.interval > 6);
+        entry.review(2, 30);
+        assert_eq!((entry.repetitions, entry.interval, entry.due_day), (0, 1, 31));
+        assert_eq!((entry.attempts, entry.failures), (4, 1));
+        entry.review(4, 31);
+        assert_eq!(entry.interval, 1);
+    }
+}
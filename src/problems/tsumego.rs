@@ -0,0 +1,364 @@
+use crate::actions::{FrameCameraEvent, KeyInputMap, KeyInputState, key_name};
+use crate::board::{
+    BoardState, GameBoard, GameBoardEffect, GameBoardEffectType, GameBoardMove, MoveNumbers, SgfCollection, SgfTree,
+    point_to_sgf, set_position, space_to_world, try_move, SPACING,
+};
+use crate::game_state::{GameMode, GameState};
+use crate::loading::PreLoadingState;
+use crate::problems::{ReviewSchedule, review_today};
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+const TSUMEGO_PROBLEM_SET: &str = "problems/beginner.sgf";
+// seconds before the opponent answers a move
+const TSUMEGO_RESPONSE_DELAY: f32 = 0.5;
+// empty lines left around the problem stones in the play area
+const TSUMEGO_AREA_MARGIN: usize = 2;
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Default)]
+pub enum TsumegoResult {
+    #[default]
+    Unsolved,
+    Solved,
+    Failed,
+}
+
+#[derive(Default, Resource)]
+pub struct TsumegoState {
+    pub problem_set: Handle<SgfCollection>,
+    pub index: usize,
+    // current node of the problem's game tree
+    pub node: usize,
+    pub player: usize,
+    pub status: TsumegoResult,
+    // latest result of every problem in the set
+    pub results: Vec<TsumegoResult>,
+    // problems served in this session, all of them or only the ones due for review
+    pub queue: Vec<usize>,
+    pub queue_position: usize,
+    response: Option<(f32, usize)>,
+}
+
+// stable id of a problem for the review schedule, a hash of the board size, the player to move
+// and the setup stones so reordering or adding problems in the set keeps every saved review
+pub fn problem_id(problem: &SgfTree) -> String {
+    let mut stones: Vec<String> = problem.setup_stones(0).iter()
+        .filter_map(|(space, player)| Some(format!("{}{}", ["B", "W"][(*player)?], point_to_sgf(*space))))
+        .collect();
+    stones.sort();
+    let setup = format!("{} {} {}", problem.size(), problem_player(problem), stones.join(","));
+    // fnv-1a, the same on every platform and compiler
+    let hash = setup.bytes().fold(0xcbf29ce484222325u64, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3));
+    format!("{}#{:016x}", TSUMEGO_PROBLEM_SET, hash)
+}
+
+fn is_problem_mode(game_mode: &GameMode) -> bool {
+    matches!(game_mode, GameMode::Tsumego | GameMode::DailyReview)
+}
+
+#[derive(Component)]
+pub struct TsumegoHud;
+
+pub struct TsumegoPlugin;
+
+impl Plugin for TsumegoPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<TsumegoState>()
+            .add_system_set(SystemSet::on_enter(GameState::Running)
+                .with_system(setup_tsumego))
+            .add_system_set(SystemSet::on_update(GameState::Running)
+                .with_system(update_tsumego)
+                .with_system(update_tsumego_hud.after(update_tsumego)))
+            ;
+    }
+}
+
+fn setup_tsumego(
+    mut commands: Commands,
+    game_mode: Res<GameMode>,
+    asset_server: Res<AssetServer>,
+    pre_loading_state: Res<PreLoadingState>,
+    mut tsumego: ResMut<TsumegoState>,
+) {
+    if !is_problem_mode(&game_mode) {
+        return;
+    }
+    *tsumego = TsumegoState {
+        problem_set: asset_server.load(TSUMEGO_PROBLEM_SET),
+        ..default()
+    };
+
+    commands.spawn(TextBundle {
+        text: Text::from_section("Loading problems...", TextStyle {
+            font: pre_loading_state.font_handle.clone(),
+            font_size: 22.0,
+            color: Color::rgb(0.9, 0.9, 0.9),
+        }),
+        style: Style {
+            position_type: PositionType::Absolute,
+            position: UiRect { left: Val::Px(12.0), top: Val::Px(12.0), ..default() },
+            ..default()
+        },
+        ..default()
+    }).insert(TsumegoHud);
+}
+
+// starts problems when the set is loaded or on next, previous and retry, follows the
+// player's moves through the problem tree and plays the answers
+#[allow(clippy::too_many_arguments)]
+fn update_tsumego(
+    game_mode: Res<GameMode>,
+    key_state: Res<KeyInputState>,
+    time: Res<Time>,
+    problem_sets: Res<Assets<SgfCollection>>,
+    mut tsumego: ResMut<TsumegoState>,
+    mut board_state: ResMut<BoardState>,
+    mut game_board: ResMut<GameBoard>,
+    mut move_numbers: ResMut<MoveNumbers>,
+    mut review_schedule: ResMut<ReviewSchedule>,
+    mut board_effects: ParamSet<(EventReader<GameBoardEffect>, EventWriter<GameBoardEffect>)>,
+    mut frame_events: EventWriter<FrameCameraEvent>,
+) {
+    if !is_problem_mode(&game_mode) {
+        return;
+    }
+    let Some(problem_set) = problem_sets.get(&tsumego.problem_set) else {
+        return;
+    };
+    let problem_count = problem_set.trees.len();
+
+    let next_position = if tsumego.results.len() != problem_count {
+        let today = review_today();
+        tsumego.results = vec![TsumegoResult::Unsolved; problem_count];
+        tsumego.queue = (0..problem_count)
+            .filter(|index| *game_mode != GameMode::DailyReview || review_schedule.is_due(&problem_id(&problem_set.trees[*index]), today))
+            .collect();
+        Some(0)
+    } else if tsumego.queue.is_empty() {
+        None
+    } else if key_state.problem_next {
+        Some((tsumego.queue_position + 1) % tsumego.queue.len())
+    } else if key_state.problem_previous {
+        Some((tsumego.queue_position + tsumego.queue.len() - 1) % tsumego.queue.len())
+    } else if key_state.problem_retry {
+        Some(tsumego.queue_position)
+    } else {
+        None
+    };
+    let next_index = next_position.and_then(|position| {
+        tsumego.queue_position = position;
+        tsumego.queue.get(position).copied()
+    });
+
+    if let Some(index) = next_index {
+        let problem = &problem_set.trees[index];
+        tsumego.index = index;
+        tsumego.node = 0;
+        tsumego.status = TsumegoResult::Unsolved;
+        tsumego.response = None;
+        tsumego.player = problem_player(problem);
+
+        let spaces: HashMap<(usize,usize),usize> = problem.setup_stones(0).iter()
+            .filter_map(|(space, player)| Some((*space, (*player)?)))
+            .collect();
+        // stones of a problem on another board size are taken off before the board changes
+        if game_board.size != problem.size() {
+            board_effects.p1().send_batch(set_position(&mut game_board, HashMap::new()));
+            game_board.size = problem.size();
+        }
+        board_effects.p1().send_batch(set_position(&mut game_board, spaces));
+        game_board.prisoners = [0, 0];
+        // the setup stones are not moves of the player
+        board_effects.p0().clear();
+        board_state.player_turn = tsumego.player;
+        move_numbers.reset_to(HashMap::new(), 0);
+        board_state.locked = false;
+
+        let (min, max) = problem_area(problem, game_board.size);
+        board_state.play_area = Some((min, max));
+        frame_events.send(FrameCameraEvent {
+            center: (space_to_world(min, game_board.size) + space_to_world(max, game_board.size)) / 2.,
+            extent: SPACING * (max.0 - min.0).max(max.1 - min.1) as f32,
+        });
+        return;
+    }
+
+    let problem = &problem_set.trees[tsumego.index];
+    let player = tsumego.player;
+    let played: Vec<GameBoardMove> = board_effects.p0().iter().filter_map(|effect| match effect.effect {
+        GameBoardEffectType::AddPiece(added) if added.player == player => Some(added),
+        _ => None,
+    }).collect();
+    for played_move in played {
+        if tsumego.status != TsumegoResult::Unsolved || tsumego.response.is_some() {
+            continue;
+        }
+        match problem.child_with_move(tsumego.node, played_move) {
+            Some(child) => {
+                tsumego.node = child;
+                if let Some(&reply) = problem.nodes[child].children.first() {
+                    tsumego.response = Some((TSUMEGO_RESPONSE_DELAY, reply));
+                    board_state.locked = true;
+                } else {
+                    finish_problem(&mut tsumego, &mut board_state, &mut review_schedule, problem, is_correct(problem, child));
+                }
+            }
+            // a move the problem does not know about
+            None => finish_problem(&mut tsumego, &mut board_state, &mut review_schedule, problem, false),
+        }
+    }
+
+    if let Some((delay, reply)) = tsumego.response {
+        let delay = delay - time.delta_seconds();
+        if delay > 0. {
+            tsumego.response = Some((delay, reply));
+            return;
+        }
+        tsumego.response = None;
+        tsumego.node = reply;
+        board_state.locked = false;
+        if let Some(reply_move) = problem.node_move(reply) {
+            board_effects.p1().send_batch(try_move(&mut game_board, reply_move));
+        }
+        if problem.nodes[reply].children.is_empty() {
+            finish_problem(&mut tsumego, &mut board_state, &mut review_schedule, problem, is_correct(problem, reply));
+        }
+    }
+}
+
+// only the first attempt of a session counts towards the review schedule
+fn finish_problem(tsumego: &mut TsumegoState, board_state: &mut BoardState, review_schedule: &mut ReviewSchedule, problem: &SgfTree, correct: bool) {
+    tsumego.status = if correct { TsumegoResult::Solved } else { TsumegoResult::Failed };
+    let index = tsumego.index;
+    if tsumego.results[index] == TsumegoResult::Unsolved {
+        review_schedule.record_attempt(&problem_id(problem), correct, review_today());
+    }
+    tsumego.results[index] = tsumego.status;
+    board_state.locked = true;
+}
+
+fn update_tsumego_hud(
+    tsumego: Res<TsumegoState>,
+    keymap: Res<KeyInputMap>,
+    problem_sets: Res<Assets<SgfCollection>>,
+    mut hud_query: Query<&mut Text, With<TsumegoHud>>,
+) {
+    if !tsumego.is_changed() && !keymap.is_changed() {
+        return;
+    }
+    if problem_sets.get(&tsumego.problem_set).is_some() && tsumego.queue.is_empty() {
+        for mut text in hud_query.iter_mut() {
+            text.sections[0].value = "No problems are due for review today".to_string();
+        }
+        return;
+    }
+    let Some(problem) = problem_sets.get(&tsumego.problem_set).and_then(|set| set.trees.get(tsumego.index)) else {
+        return;
+    };
+    let solved = tsumego.queue.iter().filter(|index| tsumego.results[**index] == TsumegoResult::Solved).count();
+    let status = match tsumego.status {
+        TsumegoResult::Unsolved => format!("{} to play", if tsumego.player == 0 { "Black" } else { "White" }),
+        TsumegoResult::Solved => "Solved!".to_string(),
+        TsumegoResult::Failed => "Failed".to_string(),
+    };
+    let comment = problem.property(tsumego.node, "C").unwrap_or("");
+    for mut text in hud_query.iter_mut() {
+        text.sections[0].value = format!(
+            "{} ({}/{}, {} solved)\n{}\n{}\n{}: next  {}: back  {}: retry",
+            problem.property(0, "GN").unwrap_or("Problem"),
+            tsumego.queue_position + 1,
+            tsumego.queue.len(),
+            solved,
+            status,
+            comment,
+            key_name(keymap.key_problem_next),
+            key_name(keymap.key_problem_previous),
+            key_name(keymap.key_problem_retry),
+        );
+    }
+}
+
+// the player to move, from PL or the colour of the first move in the tree
+fn problem_player(problem: &SgfTree) -> usize {
+    match problem.property(0, "PL") {
+        Some("W") => 1,
+        Some("B") => 0,
+        _ => problem.nodes[0].children.first()
+            .and_then(|child| problem.node_move(*child))
+            .map_or(0, |mv| mv.player),
+    }
+}
+
+// a leaf is correct when it is marked as a good move, or when unmarked its comment opens with
+// "correct" or "right" as a word of its own, so "Incorrect" does not count
+fn is_correct(problem: &SgfTree, node: usize) -> bool {
+    if ["TE", "GB"].iter().any(|good| problem.property(node, good).is_some()) {
+        return true;
+    }
+    if ["BM", "DO"].iter().any(|bad| problem.property(node, bad).is_some()) {
+        return false;
+    }
+    let comment = problem.property(node, "C").unwrap_or("").trim_start().to_lowercase();
+    let first_word = comment.split(|c: char| !c.is_alphabetic()).next().unwrap_or("");
+    first_word == "correct" || first_word == "right"
+}
+
+// bounding rectangle of every stone and move in the problem, with a margin
+fn problem_area(problem: &SgfTree, board_size: usize) -> ((usize,usize),(usize,usize)) {
+    let mut spaces: Vec<(usize,usize)> = problem.setup_stones(0).iter().map(|(space, _)| *space).collect();
+    spaces.extend((0..problem.nodes.len()).filter_map(|node| problem.node_move(node)).map(|mv| mv.space));
+    if spaces.is_empty() {
+        return ((0, 0), (board_size - 1, board_size - 1));
+    }
+    let min = (
+        spaces.iter().map(|s| s.0).min().unwrap().saturating_sub(TSUMEGO_AREA_MARGIN),
+        spaces.iter().map(|s| s.1).min().unwrap().saturating_sub(TSUMEGO_AREA_MARGIN),
+    );
+    let max = (
+        (spaces.iter().map(|s| s.0).max().unwrap() + TSUMEGO_AREA_MARGIN).min(board_size - 1),
+        (spaces.iter().map(|s| s.1).max().unwrap() + TSUMEGO_AREA_MARGIN).min(board_size - 1),
+    );
+    (min, max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::parse_sgf;
+
+    fn leaf_correct(leaf: &str) -> bool {
+        let problem = parse_sgf(&format!("(;SZ[9]AB[aa];B[ba]{})", leaf)).unwrap().remove(0);
+        is_correct(&problem, problem.nodes.len() - 1)
+    }
+
+    #[test]
+    fn good_move_marks_are_correct() {
+        assert!(leaf_correct("TE[1]"));
+        assert!(leaf_correct("GB[1]C[Wrong]"));
+    }
+
+    #[test]
+    fn bad_move_marks_are_wrong() {
+        assert!(!leaf_correct("BM[1]C[Correct]"));
+        assert!(!leaf_correct("DO[]"));
+    }
+
+    #[test]
+    fn problem_ids_follow_the_setup_not_the_order() {
+        let problems = parse_sgf("(;SZ[9]PL[B]AB[aa][bb]AW[cc];B[dd])(;SZ[9]PL[B]AW[cc]AB[bb][aa];B[ee])(;SZ[9]PL[W]AB[aa][bb]AW[cc])").unwrap();
+        assert_eq!(problem_id(&problems[0]), problem_id(&problems[1]));
+        assert_ne!(problem_id(&problems[0]), problem_id(&problems[2]));
+        assert!(problem_id(&problems[0]).starts_with(TSUMEGO_PROBLEM_SET));
+    }
+
+    #[test]
+    fn comments_count_only_when_they_open_with_the_word() {
+        assert!(leaf_correct("C[Correct, black lives.]"));
+        assert!(leaf_correct("C[  right!]"));
+        assert!(!leaf_correct("C[Incorrect, white escapes.]"));
+        assert!(!leaf_correct("C[Wrong, the right side dies.]"));
+        assert!(!leaf_correct("C[Not correct]"));
+        assert!(!leaf_correct(""));
+    }
+}
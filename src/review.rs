@@ -0,0 +1,4 @@
+mod game_review;
+pub use game_review::*;
+mod joseki;
+pub use joseki::*;
@@ -0,0 +1,219 @@
+use crate::actions::{GameAction, KeyInputState};
+use crate::board::{
+    BoardState, GameBoard, GameBoardEffect, GameBoardEffectType, MoveNumbers, SgfCursor, SgfTree, TreeStep,
+    set_position, space_name,
+};
+use crate::game_state::{GameMode, GameState};
+use crate::loading::PreLoadingState;
+use crate::menu::ButtonColors;
+use bevy::{input::mouse::MouseWheel, prelude::*};
+
+// moves shown in the move list around the current one
+const MOVE_LIST_LENGTH: usize = 16;
+
+// every move of a free game as a tree, playing from an earlier position starts a variation
+#[derive(Default, Resource)]
+pub struct GameRecord {
+    pub tree: SgfTree,
+    pub cursor: SgfCursor,
+}
+
+#[derive(Component)]
+pub struct MoveList;
+
+#[derive(Component)]
+pub struct MoveListButton {
+    node: usize,
+}
+
+pub struct GameReviewPlugin;
+
+impl Plugin for GameReviewPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<GameRecord>()
+            .add_system_set(SystemSet::on_enter(GameState::Running)
+                .with_system(setup_game_record))
+            .add_system_set(SystemSet::on_update(GameState::Running)
+                .with_system(update_game_record)
+                .with_system(update_move_list.after(update_game_record)))
+            ;
+    }
+}
+
+fn setup_game_record(
+    mut commands: Commands,
+    game_mode: Res<GameMode>,
+    game_board: Res<GameBoard>,
+    mut record: ResMut<GameRecord>,
+) {
+    if *game_mode != GameMode::Free {
+        return;
+    }
+    *record = GameRecord {
+        tree: SgfTree::with_size(game_board.size),
+        cursor: SgfCursor::default(),
+    };
+
+    commands.spawn(NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            position: UiRect { right: Val::Px(12.0), top: Val::Px(12.0), ..default() },
+            flex_direction: FlexDirection::Column,
+            ..default()
+        },
+        background_color: Color::NONE.into(),
+        ..default()
+    })
+    // hovered the list takes the wheel from the camera zoom
+    .insert(Interaction::default())
+    .insert(MoveList);
+}
+
+// records moves played on the board and moves through the record with the review keys,
+// the mouse wheel over the move list and its buttons, undo takes back the move shown
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn update_game_record(
+    game_mode: Res<GameMode>,
+    key_state: Res<KeyInputState>,
+    mut actions: EventReader<GameAction>,
+    mut mouse_wheel_events: EventReader<MouseWheel>,
+    mut record: ResMut<GameRecord>,
+    mut board_state: ResMut<BoardState>,
+    mut game_board: ResMut<GameBoard>,
+    mut move_numbers: ResMut<MoveNumbers>,
+    mut board_effects: ParamSet<(EventReader<GameBoardEffect>, EventWriter<GameBoardEffect>)>,
+    move_list_query: Query<(&Interaction, &MoveListButton), Changed<Interaction>>,
+    hover_query: Query<&Interaction, Or<(With<MoveList>, With<MoveListButton>)>>,
+) {
+    if *game_mode != GameMode::Free {
+        return;
+    }
+
+    let mut steps: Vec<TreeStep> = key_state.review_step.into_iter().collect();
+    let over_move_list = hover_query.iter().any(|interaction| *interaction != Interaction::None);
+    for wheel in mouse_wheel_events.iter().filter(|_| over_move_list) {
+        if wheel.y > 0. {
+            steps.push(TreeStep::Back);
+        } else if wheel.y < 0. {
+            steps.push(TreeStep::Forward);
+        }
+    }
+    let mut cursor = record.cursor;
+    let mut sync = false;
+    for step in steps {
+        sync |= cursor.step(&record.tree, step);
+    }
+    if actions.iter().filter(|action| **action == GameAction::Undo).count() > 0 {
+        if let Some(parent) = record.tree.nodes[cursor.node].parent {
+            // a move that was just played leaves the record, older ones stay as a variation
+            record.tree.remove_last_leaf(cursor.node);
+            cursor = SgfCursor { node: parent, branch: 0 };
+            sync = true;
+        }
+    }
+    for (interaction, button) in move_list_query.iter() {
+        if *interaction == Interaction::Clicked {
+            cursor = SgfCursor { node: button.node, branch: 0 };
+            sync = true;
+        }
+    }
+
+    if sync {
+        record.cursor = cursor;
+        // only the stones that differ from the shown position are added or removed
+        let position = record.tree.position_at(record.cursor.node);
+        board_effects.p1().send_batch(set_position(&mut game_board, position.spaces));
+        game_board.ko = position.ko;
+        game_board.prisoners = position.prisoners;
+        // replayed stones are not moves made on the board
+        board_effects.p0().clear();
+        board_state.player_turn = record.cursor.next_player(&record.tree);
+        move_numbers.reset_to(record.tree.move_numbers(record.cursor.node), record.tree.move_number(record.cursor.node));
+        return;
+    }
+
+    // a player toggle that does not follow a stone is a pass
+    let mut after_stone = false;
+    for effect in board_effects.p0().iter() {
+        let record = &mut *record;
+        let node = match effect.effect {
+            GameBoardEffectType::AddPiece(added) => {
+                after_stone = true;
+                record.tree.add_move(record.cursor.node, added)
+            }
+            GameBoardEffectType::TogglePlayer if !after_stone => {
+                let player = record.tree.node_player(record.cursor.node).map_or(0, |last| (last + 1) % 2);
+                record.tree.add_pass(record.cursor.node, player)
+            }
+            GameBoardEffectType::TogglePlayer => {
+                after_stone = false;
+                continue;
+            }
+            GameBoardEffectType::RemovePiece(_) => continue,
+        };
+        record.cursor = SgfCursor { node, branch: 0 };
+    }
+}
+
+// rebuilds the clickable list of moves along the current variation
+fn update_move_list(
+    mut commands: Commands,
+    record: Res<GameRecord>,
+    pre_loading_state: Res<PreLoadingState>,
+    button_colors: Res<ButtonColors>,
+    move_list_query: Query<Entity, With<MoveList>>,
+) {
+    if !record.is_changed() {
+        return;
+    }
+    let Ok(move_list) = move_list_query.get_single() else {
+        return;
+    };
+    commands.entity(move_list).despawn_descendants();
+
+    let tree = &record.tree;
+    // the path to the current move continued along the selected variation
+    let mut line = tree.path_to(record.cursor.node);
+    let mut end = record.cursor;
+    while end.step(tree, TreeStep::Forward) {
+        line.push(end.node);
+    }
+    let line: Vec<usize> = line.into_iter().filter(|node| tree.node_player(*node).is_some()).collect();
+    let current = line.iter().position(|node| *node == record.cursor.node);
+    let first = current.map_or(0, |current| current.saturating_sub(MOVE_LIST_LENGTH / 2))
+        .min(line.len().saturating_sub(MOVE_LIST_LENGTH));
+
+    commands.entity(move_list).with_children(|parent| {
+        for (index, node) in line.iter().enumerate().skip(first).take(MOVE_LIST_LENGTH) {
+            let player = tree.node_player(*node).unwrap();
+            let point = tree.node_move(*node).map_or("pass".to_string(), |node_move| space_name(node_move.space, tree.size()));
+            // marks moves that have other variations
+            let siblings = tree.nodes[*node].parent.map_or(1, |parent| tree.nodes[parent].children.len());
+            let label = format!("{}. {} {}{}", index + 1, ["B", "W"][player], point, if siblings > 1 { " *" } else { "" });
+            let selected = Some(index) == current;
+            parent.spawn(ButtonBundle {
+                style: Style {
+                    size: Size::new(Val::Px(140.0), Val::Px(26.0)),
+                    margin: UiRect::all(Val::Px(1.0)),
+                    padding: UiRect::horizontal(Val::Px(8.0)),
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: if selected { button_colors.hovered.into() } else { button_colors.normal.into() },
+                ..default()
+            })
+            .insert(MoveListButton { node: *node })
+            .with_children(|parent| {
+                parent.spawn(TextBundle {
+                    text: Text::from_section(label, TextStyle {
+                        font: pre_loading_state.font_handle.clone(),
+                        font_size: 18.0,
+                        color: Color::rgb(0.9, 0.9, 0.9),
+                    }),
+                    ..default()
+                });
+            });
+        }
+    });
+}
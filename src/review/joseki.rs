@@ -0,0 +1,346 @@
+use crate::actions::{FrameCameraEvent, KeyInputMap, KeyInputState, key_name};
+use crate::board::{
+    BoardState, GameBoard, GameBoardEffect, GameBoardEffectType, GameBoardMove, MoveNumbers, SgfCollection, SgfCursor, StoneMeshes, TreeStep,
+    set_position, space_to_world, spawn_world_label, SPACING,
+};
+use crate::game_state::{GameMode, GameState};
+use crate::loading::PreLoadingState;
+use bevy::prelude::*;
+
+const JOSEKI_TREE: &str = "joseki/corner.sgf";
+// the corner the joseki tree is written for, min and max inclusive
+const JOSEKI_AREA: ((usize,usize),(usize,usize)) = ((0, 0), (10, 10));
+
+// one of the 8 ways to turn or mirror the board, so the tree's corner can be played in any corner
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Symmetry {
+    swap: bool,
+    flip_x: bool,
+    flip_y: bool,
+}
+
+impl Symmetry {
+    // the identity comes first, so a sequence that fits several corners is shown as written
+    fn all() -> impl Iterator<Item = Symmetry> {
+        (0..8).map(|i| Symmetry { swap: i & 4 != 0, flip_x: i & 1 != 0, flip_y: i & 2 != 0 })
+    }
+
+    // from a point on the board into the frame the tree is written in
+    fn apply(self, space: (usize,usize), size: usize) -> (usize,usize) {
+        let (mut x, mut y) = if self.swap { (space.1, space.0) } else { space };
+        if self.flip_x { x = size - 1 - x; }
+        if self.flip_y { y = size - 1 - y; }
+        (x, y)
+    }
+
+    // from a point in the tree back onto the board
+    fn invert(self, space: (usize,usize), size: usize) -> (usize,usize) {
+        let (mut x, mut y) = space;
+        if self.flip_x { x = size - 1 - x; }
+        if self.flip_y { y = size - 1 - y; }
+        if self.swap { (y, x) } else { (x, y) }
+    }
+}
+
+#[derive(Default, Resource)]
+pub struct JosekiState {
+    pub tree: Handle<SgfCollection>,
+    pub cursor: SgfCursor,
+    // stones played since the position left the book, the cursor stays on the last book node
+    pub off_book: Vec<(usize,usize)>,
+    // the symmetries that fit every book move played so far, the first one places the tree on the board
+    symmetries: Vec<Symmetry>,
+    loaded: bool,
+    ghost_materials: [Handle<StandardMaterial>; 2],
+    selected_materials: [Handle<StandardMaterial>; 2],
+}
+
+impl JosekiState {
+    fn symmetry(&self) -> Symmetry {
+        self.symmetries.first().copied().unwrap_or_default()
+    }
+}
+
+#[derive(Component)]
+pub struct JosekiMarker;
+
+#[derive(Component)]
+pub struct JosekiHud;
+
+pub struct JosekiPlugin;
+
+impl Plugin for JosekiPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<JosekiState>()
+            .add_system_set(SystemSet::on_enter(GameState::Running)
+                .with_system(setup_joseki))
+            .add_system_set(SystemSet::on_update(GameState::Running)
+                .with_system(update_joseki)
+                .with_system(update_joseki_markers.after(update_joseki))
+                .with_system(update_joseki_hud.after(update_joseki)))
+            ;
+    }
+}
+
+fn setup_joseki(
+    mut commands: Commands,
+    game_mode: Res<GameMode>,
+    asset_server: Res<AssetServer>,
+    pre_loading_state: Res<PreLoadingState>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut joseki: ResMut<JosekiState>,
+) {
+    if *game_mode != GameMode::Joseki {
+        return;
+    }
+    let mut ghost_material = |color: Color| materials.add(StandardMaterial {
+        base_color: color,
+        alpha_mode: AlphaMode::Blend,
+        unlit: true,
+        ..default()
+    });
+    *joseki = JosekiState {
+        tree: asset_server.load(JOSEKI_TREE),
+        ghost_materials: [ghost_material(Color::rgba(0.1, 0.1, 0.1, 0.35)), ghost_material(Color::rgba(1.0, 1.0, 1.0, 0.35))],
+        selected_materials: [ghost_material(Color::rgba(0.1, 0.1, 0.1, 0.75)), ghost_material(Color::rgba(1.0, 1.0, 1.0, 0.75))],
+        ..default()
+    };
+
+    commands.spawn(TextBundle {
+        text: Text::from_section("Loading joseki...", TextStyle {
+            font: pre_loading_state.font_handle.clone(),
+            font_size: 22.0,
+            color: Color::rgb(0.9, 0.9, 0.9),
+        }),
+        style: Style {
+            position_type: PositionType::Absolute,
+            position: UiRect { left: Val::Px(12.0), top: Val::Px(12.0), ..default() },
+            max_size: Size::new(Val::Px(520.0), Val::Undefined),
+            ..default()
+        },
+        ..default()
+    }).insert(JosekiHud);
+}
+
+// follows moves played on the board through the joseki tree and applies branch navigation
+#[allow(clippy::too_many_arguments)]
+fn update_joseki(
+    game_mode: Res<GameMode>,
+    key_state: Res<KeyInputState>,
+    trees: Res<Assets<SgfCollection>>,
+    mut joseki: ResMut<JosekiState>,
+    mut board_state: ResMut<BoardState>,
+    mut game_board: ResMut<GameBoard>,
+    mut move_numbers: ResMut<MoveNumbers>,
+    mut board_effects: ParamSet<(EventReader<GameBoardEffect>, EventWriter<GameBoardEffect>)>,
+    mut frame_events: EventWriter<FrameCameraEvent>,
+) {
+    if *game_mode != GameMode::Joseki {
+        return;
+    }
+    let Some(tree) = trees.get(&joseki.tree).and_then(|collection| collection.trees.first()) else {
+        return;
+    };
+
+    let size = tree.size();
+    let mut sync = false;
+    if !joseki.loaded {
+        joseki.loaded = true;
+        sync = true;
+        // the opening may be played in any corner
+        board_state.play_area = None;
+        frame_joseki_corner(joseki.symmetry(), size, &mut frame_events);
+    } else if let Some(step) = key_state.review_step {
+        if !joseki.off_book.is_empty() {
+            // any navigation first returns to the last position in the book
+            joseki.off_book.clear();
+            sync = true;
+            if step == TreeStep::Start {
+                joseki.cursor = SgfCursor::default();
+            }
+        } else {
+            sync = joseki.cursor.step(tree, step);
+        }
+    }
+
+    if sync {
+        // the book is shown in the corner it was last played in
+        let symmetry = joseki.symmetry();
+        let position = tree.position_at(joseki.cursor.node);
+        let spaces = position.spaces.into_iter().map(|(space, player)| (symmetry.invert(space, size), player)).collect();
+        board_effects.p1().send_batch(set_position(&mut game_board, spaces));
+        game_board.ko = position.ko.map(|ko| symmetry.invert(ko, size));
+        game_board.prisoners = position.prisoners;
+        // replayed stones are not moves made on the board
+        board_effects.p0().clear();
+        board_state.player_turn = joseki.cursor.next_player(tree);
+        let numbers = tree.move_numbers(joseki.cursor.node).into_iter().map(|(space, number)| (symmetry.invert(space, size), number)).collect();
+        move_numbers.reset_to(numbers, tree.move_number(joseki.cursor.node));
+        return;
+    }
+
+    let played: Vec<_> = board_effects.p0().iter().filter_map(|effect| match effect.effect {
+        GameBoardEffectType::AddPiece(added) => Some(added),
+        _ => None,
+    }).collect();
+    let shown_symmetry = joseki.symmetry();
+    for played_move in played {
+        let node = joseki.cursor.node;
+        // an opening move may start the book in any corner
+        let mut symmetries = if node == 0 { Symmetry::all().collect() } else { joseki.symmetries.clone() };
+        let in_tree = |symmetry: &Symmetry| GameBoardMove { space: symmetry.apply(played_move.space, size), ..played_move };
+        let child = if joseki.off_book.is_empty() {
+            symmetries.iter().find_map(|symmetry| tree.child_with_move(node, in_tree(symmetry)))
+        } else {
+            None
+        };
+        match child {
+            Some(child) => {
+                symmetries.retain(|symmetry| tree.child_with_move(node, in_tree(symmetry)) == Some(child));
+                joseki.symmetries = symmetries;
+                joseki.cursor = SgfCursor { node: child, branch: 0 };
+            }
+            None => joseki.off_book.push(played_move.space),
+        }
+    }
+    // mirroring along the diagonal keeps the corner, so only a new corner moves the camera
+    if joseki.symmetry().invert(JOSEKI_AREA.0, size) != shown_symmetry.invert(JOSEKI_AREA.0, size) {
+        frame_joseki_corner(joseki.symmetry(), size, &mut frame_events);
+    }
+}
+
+// points the camera at the corner the book is shown in
+fn frame_joseki_corner(symmetry: Symmetry, size: usize, frame_events: &mut EventWriter<FrameCameraEvent>) {
+    let (a, b) = (symmetry.invert(JOSEKI_AREA.0, size), symmetry.invert(JOSEKI_AREA.1, size));
+    let (min, max) = ((a.0.min(b.0), a.1.min(b.1)), (a.0.max(b.0), a.1.max(b.1)));
+    frame_events.send(FrameCameraEvent {
+        center: (space_to_world(min, size) + space_to_world(max, size)) / 2.,
+        extent: SPACING * (max.0 - min.0) as f32,
+    });
+}
+
+// numbered ghost stones for every known continuation while in the book, a marker where play left it
+fn update_joseki_markers(
+    mut commands: Commands,
+    game_mode: Res<GameMode>,
+    joseki: Res<JosekiState>,
+    trees: Res<Assets<SgfCollection>>,
+    stone_meshes: Res<StoneMeshes>,
+    pre_loading_state: Res<PreLoadingState>,
+    markers_query: Query<Entity, With<JosekiMarker>>,
+) {
+    if *game_mode != GameMode::Joseki || !joseki.is_changed() {
+        return;
+    }
+    for ent in markers_query.iter() {
+        commands.entity(ent).despawn_recursive();
+    }
+    let Some(tree) = trees.get(&joseki.tree).and_then(|collection| collection.trees.first()) else {
+        return;
+    };
+
+    if let Some(first_off_book) = joseki.off_book.first() {
+        let label_ent = spawn_world_label(
+            &mut commands,
+            space_to_world(*first_off_book, tree.size()) + Vec3::Y * SPACING * 1.5,
+            "Out of book".to_string(),
+            TextStyle {
+                font: pre_loading_state.font_handle.clone(),
+                font_size: 18.0,
+                color: Color::rgb(0.9, 0.1, 0.1),
+            },
+        );
+        commands.entity(label_ent).insert(JosekiMarker);
+        return;
+    }
+
+    let symmetry = joseki.symmetry();
+    for (branch, child) in tree.nodes[joseki.cursor.node].children.iter().enumerate() {
+        let Some(mut child_move) = tree.node_move(*child) else {
+            continue;
+        };
+        child_move.space = symmetry.invert(child_move.space, tree.size());
+        let selected = branch == joseki.cursor.branch;
+        let materials = if selected { &joseki.selected_materials } else { &joseki.ghost_materials };
+        commands.spawn(PbrBundle {
+            mesh: stone_meshes.mesh(),
+            material: materials[child_move.player].clone(),
+            transform: Transform::from_translation(space_to_world(child_move.space, tree.size())),
+            ..default()
+        }).insert(JosekiMarker);
+
+        let label_ent = spawn_world_label(
+            &mut commands,
+            space_to_world(child_move.space, tree.size()) + Vec3::Y * SPACING * 0.5,
+            (branch + 1).to_string(),
+            TextStyle {
+                font: pre_loading_state.font_handle.clone(),
+                font_size: if selected { 22.0 } else { 18.0 },
+                color: if selected { Color::rgb(1.0, 0.8, 0.1) } else if child_move.player == 1 { Color::BLACK } else { Color::WHITE },
+            },
+        );
+        commands.entity(label_ent).insert(JosekiMarker);
+    }
+}
+
+fn update_joseki_hud(
+    joseki: Res<JosekiState>,
+    keymap: Res<KeyInputMap>,
+    trees: Res<Assets<SgfCollection>>,
+    mut hud_query: Query<&mut Text, With<JosekiHud>>,
+) {
+    if !joseki.is_changed() && !keymap.is_changed() {
+        return;
+    }
+    let Some(tree) = trees.get(&joseki.tree).and_then(|collection| collection.trees.first()) else {
+        return;
+    };
+    let node = joseki.cursor.node;
+    let continuations = tree.nodes[node].children.len();
+    let status = if !joseki.off_book.is_empty() {
+        format!("Out of book after move {}, any navigation key returns to the book", tree.move_number(node))
+    } else if continuations == 0 {
+        "End of the known sequence".to_string()
+    } else {
+        format!("{} known continuation{}, variation {} selected", continuations,
+            if continuations == 1 { "" } else { "s" }, joseki.cursor.branch + 1)
+    };
+    for mut text in hud_query.iter_mut() {
+        text.sections[0].value = format!(
+            "{} (move {})\n{}\n{}\n{}/{}: back/forward  {}/{}: variation  {}/{}: start/end",
+            tree.property(0, "GN").unwrap_or("Joseki"),
+            tree.move_number(node),
+            tree.property(node, "C").unwrap_or(""),
+            status,
+            key_name(keymap.key_review_back),
+            key_name(keymap.key_review_forward),
+            key_name(keymap.key_branch_previous),
+            key_name(keymap.key_branch_next),
+            key_name(keymap.key_review_start),
+            key_name(keymap.key_review_end),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn symmetries_reach_every_corner_and_invert() {
+        // the 4-4 point in the upper left, seen from each symmetry
+        let mut corners: Vec<(usize,usize)> = Symmetry::all().map(|symmetry| symmetry.invert((3, 3), 19)).collect();
+        corners.sort();
+        corners.dedup();
+        assert_eq!(corners, vec![(3, 3), (3, 15), (15, 3), (15, 15)]);
+
+        for symmetry in Symmetry::all() {
+            for space in [(0, 0), (2, 3), (16, 3), (18, 9)] {
+                assert_eq!(symmetry.invert(symmetry.apply(space, 19), 19), space);
+            }
+        }
+        assert_eq!(Symmetry::all().count(), 8);
+        assert_eq!(Symmetry::all().map(|symmetry| symmetry.apply((2, 3), 19)).collect::<std::collections::HashSet<_>>().len(), 8);
+    }
+}
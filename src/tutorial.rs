@@ -0,0 +1,4 @@
+mod lessons;
+pub use lessons::*;
+mod tutorial_state;
+pub use tutorial_state::*;
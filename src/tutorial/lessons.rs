@@ -0,0 +1,139 @@
+// one scripted step of the rules tutorial, played in the 9x9 corner of the board
+pub struct Lesson {
+    pub title: &'static str,
+    pub instruction: &'static str,
+    // rows of the lesson board, `X` black, `O` white, `.` empty
+    pub diagram: [&'static str; 9],
+    // the moves, as (column, row) of the diagram, that complete the lesson
+    pub expected: &'static [(usize,usize)],
+    // white's answer, played through try_move so illegal answers are shown as rejected
+    pub reply: Option<(usize,usize)>,
+    pub success: &'static str,
+    pub hint: &'static str,
+    // show the area score of the lesson board once it is completed
+    pub count: bool,
+}
+
+// the player is always black
+pub const LESSONS: [Lesson; 6] = [
+    Lesson {
+        title: "Placing stones",
+        instruction: "Stones are played on the intersections of the lines, not in the squares. Black plays first: place a stone on the middle point of this 9x9 corner.",
+        diagram: [
+            ". . . . . . . . .",
+            ". . . . . . . . .",
+            ". . . . . . . . .",
+            ". . . . . . . . .",
+            ". . . . . . . . .",
+            ". . . . . . . . .",
+            ". . . . . . . . .",
+            ". . . . . . . . .",
+            ". . . . . . . . .",
+        ],
+        expected: &[(4, 4)],
+        reply: Some((2, 6)),
+        success: "White answers. The players take turns, one stone at a time, and stones never move once played.",
+        hint: "The middle point is five lines in from each edge of the corner.",
+        count: false,
+    },
+    Lesson {
+        title: "Liberties",
+        instruction: "The empty points next to a stone are its liberties. White has taken two of this black stone's four liberties. Play next to it to give it more.",
+        diagram: [
+            ". . . . . . . . .",
+            ". . . . . . . . .",
+            ". . . . . . . . .",
+            ". . . . O . . . .",
+            ". . . O X . . . .",
+            ". . . . . . . . .",
+            ". . . . . . . . .",
+            ". . . . . . . . .",
+            ". . . . . . . . .",
+        ],
+        expected: &[(5, 4), (4, 5)],
+        reply: None,
+        success: "Connected stones share their liberties: your two stones now have four together.",
+        hint: "Play on one of the two empty points touching the black stone.",
+        count: false,
+    },
+    Lesson {
+        title: "Capture",
+        instruction: "The white stone has a single liberty left. Fill it to capture the stone.",
+        diagram: [
+            ". . . . . . . . .",
+            ". . . . . . . . .",
+            ". . . . . . . . .",
+            ". . . . X . . . .",
+            ". . . X O X . . .",
+            ". . . . . . . . .",
+            ". . . . . . . . .",
+            ". . . . . . . . .",
+            ". . . . . . . . .",
+        ],
+        expected: &[(4, 5)],
+        reply: None,
+        success: "Stones without liberties are captured and taken off the board.",
+        hint: "Find the one empty point next to the white stone.",
+        count: false,
+    },
+    Lesson {
+        title: "Ko",
+        instruction: "Capture the white stone in the middle of this shape.",
+        diagram: [
+            ". . . . . . . . .",
+            ". . . . . . . . .",
+            ". . . . X O . . .",
+            ". . . X O . O . .",
+            ". . . . X O . . .",
+            ". . . . . . . . .",
+            ". . . . . . . . .",
+            ". . . . . . . . .",
+            ". . . . . . . . .",
+        ],
+        expected: &[(5, 3)],
+        reply: Some((4, 3)),
+        success: "White would like to take back at once, but that would repeat the position. This is ko: White must play somewhere else first.",
+        hint: "The white stone at the centre of the shape has one liberty.",
+        count: false,
+    },
+    Lesson {
+        title: "Eyes",
+        instruction: "An eye is an empty point surrounded by one group. Give the black group a second eye so it can never be captured.",
+        diagram: [
+            ". X . . O . . . .",
+            "X X X X O . . . .",
+            "O O O O O . . . .",
+            ". . . . . . . . .",
+            ". . . . . . . . .",
+            ". . . . . . . . .",
+            ". . . . . . . . .",
+            ". . . . . . . . .",
+            ". . . . . . . . .",
+        ],
+        expected: &[(3, 0)],
+        reply: Some((2, 0)),
+        success: "White cannot play inside either eye: the stone would have no liberties and capture nothing. Two eyes means the group lives.",
+        hint: "Close off the edge of the group so the empty point beside it becomes a real eye.",
+        count: false,
+    },
+    Lesson {
+        title: "Counting",
+        instruction: "At the end, each player counts their stones plus the empty points they surround. Close the gap in Black's wall to secure the territory.",
+        diagram: [
+            ". . . . X O . . .",
+            ". . . . X O . . .",
+            ". . . . X O . . .",
+            ". . . . X O . . .",
+            ". . . . X O . . .",
+            ". . . . X O . . .",
+            ". . . . X O . . .",
+            ". . . . X O . . .",
+            ". . . . . O . . .",
+        ],
+        expected: &[(4, 8)],
+        reply: None,
+        success: "Black's area is closed. Each side counts stones plus surrounded points.",
+        hint: "Find the gap where White could reach Black's side.",
+        count: true,
+    },
+];
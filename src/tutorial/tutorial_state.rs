@@ -0,0 +1,234 @@
+use crate::actions::{FrameCameraEvent, KeyInputMap, KeyInputState, key_name};
+use crate::board::{
+    BoardState, GameBoard, GameBoardEffect, GameBoardEffectType, GameBoardMove, IllegalMove, MoveNumbers,
+    check_move, set_position, space_to_world, try_move, SPACING,
+};
+use crate::game_state::{GameMode, GameState};
+use crate::loading::PreLoadingState;
+use crate::tutorial::{Lesson, LESSONS};
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+// lessons are played on a small board so the edge is always in view
+const TUTORIAL_BOARD_SIZE: usize = 9;
+// seconds before white answers a correct move
+const TUTORIAL_RESPONSE_DELAY: f32 = 0.6;
+// seconds a wrong move stays on the board before the lesson is reset
+const TUTORIAL_RESET_DELAY: f32 = 1.2;
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Default)]
+pub enum TutorialStatus {
+    #[default]
+    Waiting,
+    Answering,
+    Wrong,
+    Done,
+}
+
+#[derive(Default, Resource)]
+pub struct TutorialState {
+    pub lesson: usize,
+    pub status: TutorialStatus,
+    pub feedback: String,
+    timer: f32,
+    // set when a lesson should be (re)started
+    restart: bool,
+}
+
+#[derive(Component)]
+pub struct TutorialHud;
+
+pub struct TutorialPlugin;
+
+impl Plugin for TutorialPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<TutorialState>()
+            .add_system_set(SystemSet::on_enter(GameState::Running)
+                .with_system(setup_tutorial))
+            .add_system_set(SystemSet::on_update(GameState::Running)
+                .with_system(update_tutorial)
+                .with_system(update_tutorial_hud.after(update_tutorial)))
+            ;
+    }
+}
+
+fn setup_tutorial(
+    mut commands: Commands,
+    game_mode: Res<GameMode>,
+    pre_loading_state: Res<PreLoadingState>,
+    mut tutorial: ResMut<TutorialState>,
+) {
+    if *game_mode != GameMode::Tutorial {
+        return;
+    }
+    *tutorial = TutorialState { restart: true, ..default() };
+
+    commands.spawn(TextBundle {
+        text: Text::from_section("", TextStyle {
+            font: pre_loading_state.font_handle.clone(),
+            font_size: 22.0,
+            color: Color::rgb(0.9, 0.9, 0.9),
+        }),
+        style: Style {
+            position_type: PositionType::Absolute,
+            position: UiRect { left: Val::Px(12.0), top: Val::Px(12.0), ..default() },
+            max_size: Size::new(Val::Px(520.0), Val::Undefined),
+            ..default()
+        },
+        ..default()
+    }).insert(TutorialHud);
+}
+
+// sets up lessons, checks the player's move against the lesson and plays white's answer
+#[allow(clippy::too_many_arguments)]
+fn update_tutorial(
+    game_mode: Res<GameMode>,
+    key_state: Res<KeyInputState>,
+    time: Res<Time>,
+    mut tutorial: ResMut<TutorialState>,
+    mut board_state: ResMut<BoardState>,
+    mut game_board: ResMut<GameBoard>,
+    mut move_numbers: ResMut<MoveNumbers>,
+    mut board_effects: ParamSet<(EventReader<GameBoardEffect>, EventWriter<GameBoardEffect>)>,
+    mut frame_events: EventWriter<FrameCameraEvent>,
+) {
+    if *game_mode != GameMode::Tutorial {
+        return;
+    }
+    let next_lesson = if key_state.problem_next && tutorial.lesson + 1 < LESSONS.len() {
+        Some(tutorial.lesson + 1)
+    } else if key_state.problem_previous && tutorial.lesson > 0 {
+        Some(tutorial.lesson - 1)
+    } else if key_state.problem_retry {
+        Some(tutorial.lesson)
+    } else {
+        None
+    };
+    if let Some(next_lesson) = next_lesson {
+        tutorial.lesson = next_lesson;
+        tutorial.feedback = String::new();
+        tutorial.restart = true;
+    }
+
+    let lesson = &LESSONS[tutorial.lesson];
+    if tutorial.restart {
+        tutorial.restart = false;
+        tutorial.status = TutorialStatus::Waiting;
+        // stones of a game on another board size are taken off before the board changes
+        if game_board.size != TUTORIAL_BOARD_SIZE {
+            board_effects.p1().send_batch(set_position(&mut game_board, HashMap::new()));
+            game_board.size = TUTORIAL_BOARD_SIZE;
+        }
+        board_effects.p1().send_batch(set_position(&mut game_board, lesson_spaces(lesson)));
+        game_board.prisoners = [0, 0];
+        // the lesson stones are not moves of the player
+        board_effects.p0().clear();
+        board_state.player_turn = 0;
+        move_numbers.reset_to(HashMap::new(), 0);
+        board_state.locked = false;
+
+        let max = (TUTORIAL_BOARD_SIZE - 1, TUTORIAL_BOARD_SIZE - 1);
+        board_state.play_area = None;
+        frame_events.send(FrameCameraEvent {
+            center: (space_to_world((0, 0), game_board.size) + space_to_world(max, game_board.size)) / 2.,
+            extent: SPACING * max.0 as f32,
+        });
+        return;
+    }
+
+    let played: Vec<GameBoardMove> = board_effects.p0().iter().filter_map(|effect| match effect.effect {
+        GameBoardEffectType::AddPiece(added) if added.player == 0 => Some(added),
+        _ => None,
+    }).collect();
+    for played_move in played {
+        if tutorial.status != TutorialStatus::Waiting {
+            continue;
+        }
+        board_state.locked = true;
+        if !lesson.expected.contains(&played_move.space) {
+            tutorial.status = TutorialStatus::Wrong;
+            tutorial.feedback = format!("Not quite. {}", lesson.hint);
+            tutorial.timer = TUTORIAL_RESET_DELAY;
+        } else if lesson.reply.is_some() {
+            tutorial.status = TutorialStatus::Answering;
+            tutorial.timer = TUTORIAL_RESPONSE_DELAY;
+        } else {
+            finish_lesson(&mut tutorial, lesson, &game_board, None);
+        }
+    }
+
+    if matches!(tutorial.status, TutorialStatus::Answering | TutorialStatus::Wrong) {
+        tutorial.timer -= time.delta_seconds();
+        if tutorial.timer > 0. {
+            return;
+        }
+        if tutorial.status == TutorialStatus::Wrong {
+            // the hint stays up while the lesson starts over
+            tutorial.restart = true;
+            return;
+        }
+        let reply = GameBoardMove { player: 1, space: lesson.reply.unwrap() };
+        let rejected = check_move(&game_board, reply).err();
+        board_effects.p1().send_batch(try_move(&mut game_board, reply));
+        finish_lesson(&mut tutorial, lesson, &game_board, rejected);
+    }
+}
+
+fn finish_lesson(tutorial: &mut TutorialState, lesson: &Lesson, game_board: &GameBoard, rejected: Option<IllegalMove>) {
+    tutorial.status = TutorialStatus::Done;
+    let mut feedback = String::new();
+    match rejected {
+        Some(IllegalMove::Ko) => feedback += "White tries to retake, but the move is rejected. ",
+        Some(IllegalMove::Suicide) => feedback += "White tries to play inside, but the move is rejected. ",
+        Some(IllegalMove::Occupied) | None => {}
+    }
+    feedback += lesson.success;
+    if lesson.count {
+        let score = game_board.area_score();
+        feedback += &format!("\nBlack {}, White {}", score[0], score[1]);
+    }
+    tutorial.feedback = feedback;
+}
+
+fn update_tutorial_hud(
+    tutorial: Res<TutorialState>,
+    keymap: Res<KeyInputMap>,
+    mut hud_query: Query<&mut Text, With<TutorialHud>>,
+) {
+    if !tutorial.is_changed() && !keymap.is_changed() {
+        return;
+    }
+    let lesson = &LESSONS[tutorial.lesson];
+    let back_retry = format!("{}: back  {}: retry", key_name(keymap.key_problem_previous), key_name(keymap.key_problem_retry));
+    let controls = if tutorial.lesson + 1 < LESSONS.len() {
+        format!("{}: next lesson  {}", key_name(keymap.key_problem_next), back_retry)
+    } else {
+        format!("That was the last lesson.\n{}", back_retry)
+    };
+    for mut text in hud_query.iter_mut() {
+        text.sections[0].value = format!(
+            "Lesson {}/{}: {}\n{}\n\n{}\n{}",
+            tutorial.lesson + 1,
+            LESSONS.len(),
+            lesson.title,
+            lesson.instruction,
+            tutorial.feedback,
+            controls,
+        );
+    }
+}
+
+fn lesson_spaces(lesson: &Lesson) -> HashMap<(usize,usize),usize> {
+    let mut spaces = HashMap::new();
+    for (j, row) in lesson.diagram.iter().enumerate() {
+        for (i, point) in row.split_whitespace().enumerate() {
+            match point {
+                "X" => { spaces.insert((i, j), 0); }
+                "O" => { spaces.insert((i, j), 1); }
+                _ => {}
+            }
+        }
+    }
+    spaces
+}